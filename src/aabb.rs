@@ -66,6 +66,25 @@ impl AABB {
         true
     }
 
+    /// Parameter range over which `r` is inside the box, if it passes through at all.
+    pub fn clip(&self, r: &Ray) -> Option<(f64, f64)> {
+        let (origin, direction) = (r.origin(), r.direction());
+        (0..3).try_fold(
+            (f64::NEG_INFINITY, f64::INFINITY),
+            |(t_enter, t_exit), axis| {
+                let interval = self.axis_interval(axis);
+                let (o, d) = (origin[axis], direction[axis]);
+                if d == 0.0 {
+                    return interval.contains(o).then_some((t_enter, t_exit));
+                }
+                let (t0, t1) = ((interval.min - o) / d, (interval.max - o) / d);
+                let (t0, t1) = if t0 <= t1 { (t0, t1) } else { (t1, t0) };
+                let (t_enter, t_exit) = (t_enter.max(t0), t_exit.min(t1));
+                (t_enter <= t_exit).then_some((t_enter, t_exit))
+            },
+        )
+    }
+
     #[inline]
     pub fn longest_axis(&self) -> i32 {
         if self.x.size() > self.y.size() && self.x.size() > self.z.size() {
@@ -2,6 +2,7 @@ use std::{cmp::Ordering, sync::Arc};
 
 use crate::{
     aabb::{AABB, EMPTY},
+    color::Color,
     interval::Interval,
     primitive::{HitRecord, Hittable, HittableList},
     ray::Ray,
@@ -104,6 +105,22 @@ impl Hittable for BVHNode {
         }
     }
 
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        if !self.bbox.hit(r, ray_t) {
+            return Color::new(1.0, 1.0, 1.0);
+        }
+
+        if let (Some(left), Some(right)) = (&self.left, &self.right) {
+            let tr = left.transmittance(r, ray_t);
+            if tr.max_component() <= 0.0 {
+                return Color::default();
+            }
+            tr * right.transmittance(r, ray_t)
+        } else {
+            self.object.as_ref().unwrap().transmittance(r, ray_t)
+        }
+    }
+
     fn bounding_box(&self) -> AABB {
         self.bbox
     }
@@ -10,21 +10,32 @@ use std::{
 use rayon::prelude::*;
 
 use crate::{
-    color::Color,
+    color::{rgb_to_spectrum, wavelength_to_rgb, Color, WAVELENGTH_MAX, WAVELENGTH_MIN},
+    environment::Environment,
     interval::Interval,
-    primitive::{Hittable, HittableList},
-    ray::Ray,
-    utils::{degrees_to_radians, random_double},
+    light::Light,
+    material::{Material, MediumStack},
+    primitive::{HitRecord, Hittable, HittableList},
+    ray::{Ray, RayDifferentials},
+    utils::{degrees_to_radians, power_heuristic, random_double},
     vec3::{Point3, Vec3},
 };
 
-#[derive(Clone, Copy)]
+/// Longest random walk followed inside a subsurface material before giving up on it.
+const MAX_WALK_STEPS: usize = 256;
+
+/// Most null collisions and false interfaces followed between two real bounces.
+const MAX_PASS_THROUGHS: usize = 4096;
+
+#[derive(Clone)]
 pub struct Camera {
     pub aspect_ratio: f64,
     pub image_width: i32,
     pub samples_per_pixel: i32,
     pub max_depth: i32,
     pub background: Color,
+    pub lights: Vec<Light>,
+    pub environment: Option<Arc<dyn Environment>>,
 
     pub vfov: f64,
     pub lookfrom: Point3,
@@ -34,6 +45,9 @@ pub struct Camera {
     pub defocus_angle: f64,
     pub focus_dist: f64,
 
+    /// Trace a single wavelength per path instead of RGB, for dispersion and spectra.
+    pub spectral: bool,
+
     pub image_height: i32,
     pub pixel_samples_scale: f64,
     pub center: Point3,
@@ -48,20 +62,177 @@ pub struct Camera {
 }
 
 impl Camera {
-    fn ray_color(&self, r: Ray, depth: i32, world: Arc<dyn Hittable>) -> Color {
+    /// `scatter_pdf` is the pdf of the bounce that produced `r`, or `None` when it was
+    /// specular (or a camera ray) and so could not have been light sampled.
+    ///
+    /// Null collisions in media and false interfaces between dielectrics carry the ray on
+    /// unchanged. They are followed in a loop rather than by recursion and do not count
+    /// against `depth`, which only limits real bounces.
+    fn ray_color(
+        &self,
+        mut r: Ray,
+        depth: i32,
+        world: Arc<dyn Hittable>,
+        media: &mut MediumStack,
+        scatter_pdf: Option<f64>,
+    ) -> Color {
         if depth <= 0 {
             return Color::default();
         }
-        if let Some(rec) = world.hit(&r, &mut Interval::new(0.001, f64::INFINITY)) {
-            let color_from_emission = rec.mat.emitted(rec.u, rec.v, rec.p);
-            if let Some((scattered, attenuation)) = rec.mat.scatter(r, &rec) {
-                let color_from_scatter =
-                    attenuation * Camera::ray_color(self, scattered, depth - 1, world);
-                return color_from_emission + color_from_scatter;
+
+        let mut color = Color::default();
+        let mut weight = Color::new(1.0, 1.0, 1.0);
+        for _ in 0..MAX_PASS_THROUGHS {
+            let (next, hit, throughput) = match media.subsurface() {
+                Some(medium) => match Self::random_walk(r, world.as_ref(), &medium) {
+                    Some((r, rec, throughput)) => (r, Some(rec), at_wavelength(throughput, &r)),
+                    None => return color,
+                },
+                None => {
+                    let hit = world.hit(&r, &mut Interval::new(0.001, f64::INFINITY));
+                    (r, hit, Color::new(1.0, 1.0, 1.0))
+                }
+            };
+            r = next;
+            let weight_here = weight * throughput;
+
+            let Some(mut rec) = hit else {
+                return color + weight_here * self.background_color(&r, scatter_pdf);
+            };
+            rec.set_footprint(&r);
+            let color_from_emission = at_wavelength(rec.mat.emitted(&r, &rec), &r);
+            let color_from_lights = if rec.mat.is_specular() {
+                Color::default()
+            } else {
+                self.direct_lighting(&r, &rec, world.as_ref())
+            };
+            color += weight_here * (color_from_emission + color_from_lights);
+
+            let Some((scattered, attenuation, passed_through)) = rec.mat.scatter(r, &rec, media)
+            else {
+                return color;
+            };
+            let scattered = scattered.with_wavelength(r.wavelength());
+            let attenuation = at_wavelength(attenuation, &r);
+            // Passing straight on is not a new bounce, so MIS still refers to the last
+            // real one.
+            if passed_through {
+                weight = weight_here * attenuation;
+                r = scattered;
+                continue;
+            }
+
+            let pdf = if rec.mat.is_specular() {
+                None
+            } else {
+                Some(rec.mat.pdf(&r, &rec, &scattered.direction().unit_vector()))
+            };
+            let color_from_scatter =
+                attenuation * Camera::ray_color(self, scattered, depth - 1, world, media, pdf);
+            return color + weight_here * color_from_scatter;
+        }
+        color
+    }
+
+    /// Light from the environment and the sun disks along a ray that escaped the scene.
+    fn background_color(&self, r: &Ray, scatter_pdf: Option<f64>) -> Color {
+        let color_from_background = match &self.environment {
+            Some(env) => {
+                let radiance = env.radiance(&r.direction());
+                match scatter_pdf {
+                    Some(pdf) => radiance * power_heuristic(pdf, env.pdf(&r.direction())),
+                    None => radiance,
+                }
+            }
+            None => self.background,
+        };
+        let color_from_background = at_wavelength(color_from_background, r);
+
+        // Sun disks are sampled explicitly at diffuse hits, so only specular paths see them.
+        let color_from_lights = if scatter_pdf.is_none() {
+            self.lights
+                .iter()
+                .map(|light| at_wavelength(light.radiance(&r.direction()), r))
+                .sum()
+        } else {
+            Color::default()
+        };
+        color_from_background + color_from_lights
+    }
+
+    /// Walks `r` through the interior of a subsurface material until it reaches the
+    /// boundary, returning the ray that hits it, the hit and the path weight so far.
+    fn random_walk(
+        mut r: Ray,
+        world: &dyn Hittable,
+        medium: &Material,
+    ) -> Option<(Ray, HitRecord, Color)> {
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        for _ in 0..MAX_WALK_STEPS {
+            let rec = world.hit(&r, &mut Interval::new(0.001, f64::INFINITY))?;
+            let (scattered, weight) = medium.interior_step(&r, rec.t);
+            throughput = throughput * weight;
+            match scattered {
+                Some(scattered) => r = scattered.with_wavelength(r.wavelength()),
+                None => return Some((r, rec, throughput)),
+            }
+
+            // Russian roulette once the walk has lost most of its energy.
+            let survival = throughput.max_component().min(1.0);
+            if survival < 0.1 {
+                if random_double() >= survival {
+                    return None;
+                }
+                throughput /= survival;
             }
-            return color_from_emission;
         }
-        self.background
+        None
+    }
+
+    fn direct_lighting(&self, r: &Ray, rec: &HitRecord, world: &dyn Hittable) -> Color {
+        let transmittance = |direction: Vec3, distance: f64| {
+            let shadow_ray = Ray::new(rec.p, direction, r.time()).with_wavelength(r.wavelength());
+            world.transmittance(&shadow_ray, Interval::new(0.001, distance * (1.0 - 1e-6)))
+        };
+
+        let color_from_lights: Color = self
+            .lights
+            .iter()
+            .filter_map(|light| light.sample(rec.p))
+            .filter_map(|sample| {
+                let f = at_wavelength(rec.mat.eval(r, rec, &sample.direction), r);
+                if f.near_zero() {
+                    return None;
+                }
+                let tr = transmittance(sample.direction, sample.distance);
+                if tr.max_component() <= 0.0 {
+                    return None;
+                }
+                Some(at_wavelength(tr, r) * f * at_wavelength(sample.radiance, r))
+            })
+            .sum();
+
+        let color_from_environment = match &self.environment {
+            Some(env) => {
+                let (direction, light_pdf) = env.sample();
+                let f = at_wavelength(rec.mat.eval(r, rec, &direction), r);
+                let tr = if light_pdf <= 0.0 || f.near_zero() {
+                    Color::default()
+                } else {
+                    transmittance(direction, f64::INFINITY)
+                };
+                if tr.max_component() <= 0.0 {
+                    Color::default()
+                } else {
+                    let weight = power_heuristic(light_pdf, rec.mat.pdf(r, rec, &direction));
+                    let radiance = at_wavelength(env.radiance(&direction), r);
+                    at_wavelength(tr, r) * f * radiance * (weight / light_pdf)
+                }
+            }
+            None => Color::default(),
+        };
+
+        color_from_lights + color_from_environment
     }
 
     fn initialize(&mut self) {
@@ -121,7 +292,27 @@ impl Camera {
         let ray_direction = pixel_sample - ray_origin;
         let ray_time = random_double();
 
-        Ray::new(ray_origin, ray_direction, ray_time)
+        // Offset rays shrink with the sample count, since many samples per pixel average
+        // over the footprint already.
+        let spacing = (1.0 / (self.samples_per_pixel as f64).sqrt()).max(0.125);
+        let differentials = RayDifferentials {
+            rx_origin: ray_origin,
+            rx_direction: (ray_direction + spacing * self.pixel_delta_u).unit_vector(),
+            ry_origin: ray_origin,
+            ry_direction: (ray_direction + spacing * self.pixel_delta_v).unit_vector(),
+        };
+
+        Ray::new(ray_origin, ray_direction, ray_time).with_differentials(Some(differentials))
+    }
+
+    /// Wavelength for the `s`th sample of a pixel in spectral mode, stratified over the
+    /// visible range so each pixel sees the whole spectrum.
+    fn sample_wavelength(&self, s: i32) -> Option<f64> {
+        if !self.spectral {
+            return None;
+        }
+        let t = (s as f64 + random_double()) / self.samples_per_pixel as f64;
+        Some(WAVELENGTH_MIN + t * (WAVELENGTH_MAX - WAVELENGTH_MIN))
     }
 
     pub fn render(&mut self, world: Arc<HittableList>) {
@@ -145,8 +336,22 @@ impl Camera {
             );
             for i in 0..self.image_width {
                 let pixel_color = (0..self.samples_per_pixel)
-                    .map(|_s| {
-                        Camera::ray_color(self, self.get_ray(i, j), self.max_depth, world.clone())
+                    .map(|s| {
+                        let wavelength = self.sample_wavelength(s);
+                        let radiance = Camera::ray_color(
+                            self,
+                            self.get_ray(i, j).with_wavelength(wavelength),
+                            self.max_depth,
+                            world.clone(),
+                            &mut MediumStack::default(),
+                            None,
+                        );
+                        match wavelength {
+                            // Spectral paths carry a grey color holding the radiance at
+                            // their wavelength.
+                            Some(wavelength) => radiance.x * wavelength_to_rgb(wavelength),
+                            None => radiance,
+                        }
                     })
                     .sum::<Color>();
 
@@ -192,6 +397,27 @@ impl Camera {
     }
 }
 
+/// `c` as seen by a path: unchanged in RGB, or the grey value of its uplifted spectrum
+/// at the path's wavelength in spectral mode.
+///
+/// For the throughput of a media or subsurface walk this is only an approximation. The
+/// walk is sampled and weighted in RGB, its channels mixed by the channel-averaged
+/// probabilities, and only the final weight is uplifted, not the coefficients it was
+/// sampled from. Strongly colored media can come out somewhat off in spectral renders.
+#[inline]
+fn at_wavelength(c: Color, r: &Ray) -> Color {
+    match r.wavelength() {
+        Some(wavelength) => {
+            let value = rgb_to_spectrum(&c, wavelength);
+            Color::new(value, value, value)
+        }
+        None => c,
+    }
+}
+
+unsafe impl Send for Camera {}
+unsafe impl Sync for Camera {}
+
 impl Default for Camera {
     fn default() -> Self {
         Self {
@@ -200,6 +426,8 @@ impl Default for Camera {
             samples_per_pixel: 10,
             max_depth: 10,
             background: Color::default(),
+            lights: Vec::new(),
+            environment: None,
 
             vfov: 90.0,
             lookfrom: Point3::new(0.0, 0.0, 0.0),
@@ -209,6 +437,8 @@ impl Default for Camera {
             defocus_angle: 0.0,
             focus_dist: 10.0,
 
+            spectral: false,
+
             image_height: i32::default(),
             pixel_samples_scale: f64::default(),
             center: Point3::default(),
@@ -1,8 +1,16 @@
+use std::sync::OnceLock;
+
 use crate::interval::Interval;
 use crate::vec3::*;
 
 pub type Color = Vec3;
 
+/// Relative luminance of a linear Rec. 709 color.
+#[inline]
+pub fn luminance(c: &Color) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
 #[inline]
 fn linear_to_gamma(linear_comp: f64) -> f64 {
     if linear_comp > 0.0 {
@@ -27,3 +35,143 @@ impl Color {
         println!("{rbyte} {gbyte} {bbyte}");
     }
 }
+
+/// Inverse of the sRGB transfer curve, for 8-bit color stored in files.
+#[inline]
+pub fn srgb_to_linear(encoded: f64) -> f64 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// CIE XYZ to linear sRGB (D65 white).
+#[inline]
+pub fn xyz_to_rgb(Vec3 { x, y, z }: Vec3) -> Color {
+    Color::new(
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    )
+}
+
+/// CIE 1931 colour matching functions at `wavelength` nanometres, from the multi-lobe
+/// Gaussian fit of Wyman, Sloan and Shirley.
+pub fn cie_xyz(wavelength: f64) -> Vec3 {
+    let g = |mu: f64, sigma_lo: f64, sigma_hi: f64| {
+        let t = (wavelength - mu) / if wavelength < mu { sigma_lo } else { sigma_hi };
+        (-0.5 * t * t).exp()
+    };
+    Vec3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+/// Linear RGB of the reflectance spectrum `f`, integrated over the visible range and
+/// balanced so that a constant spectrum of 1 maps to white.
+pub fn spectrum_to_rgb(f: impl Fn(f64) -> f64) -> Color {
+    static WEIGHTS: OnceLock<Vec<(f64, Color)>> = OnceLock::new();
+    let weights = WEIGHTS.get_or_init(|| {
+        let samples: Vec<(f64, Color)> = (0..31)
+            .map(|i| {
+                let wavelength = 400.0 + 10.0 * i as f64;
+                (wavelength, xyz_to_rgb(cie_xyz(wavelength)))
+            })
+            .collect();
+        let white: Color = samples.iter().map(|&(_, rgb)| rgb).sum();
+        samples
+            .into_iter()
+            .map(|(wavelength, rgb)| {
+                let balanced = Color::new(rgb.x / white.x, rgb.y / white.y, rgb.z / white.z);
+                (wavelength, balanced)
+            })
+            .collect()
+    });
+
+    weights
+        .iter()
+        .map(|&(wavelength, w)| f(wavelength) * w)
+        .sum()
+}
+
+/// Range of wavelengths, in nanometres, that spectral rendering samples.
+pub const WAVELENGTH_MIN: f64 = 400.0;
+pub const WAVELENGTH_MAX: f64 = 700.0;
+
+/// Film response to unit spectral radiance at `wavelength`, scaled so that averaging it
+/// over wavelengths drawn uniformly from the sampled range maps a flat spectrum to white.
+pub fn wavelength_to_rgb(wavelength: f64) -> Color {
+    static WHITE: OnceLock<Color> = OnceLock::new();
+    let white = WHITE.get_or_init(|| {
+        (0..300)
+            .map(|i| {
+                let t = (i as f64 + 0.5) / 300.0;
+                xyz_to_rgb(cie_xyz(
+                    WAVELENGTH_MIN + t * (WAVELENGTH_MAX - WAVELENGTH_MIN),
+                ))
+            })
+            .sum::<Color>()
+            / 300.0
+    });
+    let rgb = xyz_to_rgb(cie_xyz(wavelength));
+    Color::new(rgb.x / white.x, rgb.y / white.y, rgb.z / white.z)
+}
+
+// Smits' basis spectra, sampled in ten equal bins from 380 to 720 nm.
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Value at `wavelength` of a smooth spectrum whose color is `c`, after Smits' "An
+/// RGB-to-spectrum conversion for reflectances". Grey maps to a flat spectrum.
+pub fn rgb_to_spectrum(c: &Color, wavelength: f64) -> f64 {
+    let x = (wavelength - 380.0) / 34.0 - 0.5;
+    let i = (x.floor() as i64).clamp(0, 8) as usize;
+    let t = (x - i as f64).clamp(0.0, 1.0);
+    let basis = |table: &[f64; 10]| table[i] + t * (table[i + 1] - table[i]);
+
+    let Color { x: r, y: g, z: b } = *c;
+    if r <= g && r <= b {
+        let rest = if g <= b {
+            (g - r) * basis(&SMITS_CYAN) + (b - g) * basis(&SMITS_BLUE)
+        } else {
+            (b - r) * basis(&SMITS_CYAN) + (g - b) * basis(&SMITS_GREEN)
+        };
+        r * basis(&SMITS_WHITE) + rest
+    } else if g <= r && g <= b {
+        let rest = if r <= b {
+            (r - g) * basis(&SMITS_MAGENTA) + (b - r) * basis(&SMITS_BLUE)
+        } else {
+            (b - g) * basis(&SMITS_MAGENTA) + (r - b) * basis(&SMITS_RED)
+        };
+        g * basis(&SMITS_WHITE) + rest
+    } else {
+        let rest = if r <= g {
+            (r - b) * basis(&SMITS_YELLOW) + (g - r) * basis(&SMITS_GREEN)
+        } else {
+            (g - b) * basis(&SMITS_YELLOW) + (r - g) * basis(&SMITS_RED)
+        };
+        b * basis(&SMITS_WHITE) + rest
+    }
+}
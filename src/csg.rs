@@ -0,0 +1,161 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::{AABB, EMPTY},
+    interval::Interval,
+    primitive::{HitRecord, Hittable},
+    ray::Ray,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOperation {
+    /// Inside either child.
+    Union,
+    /// Inside both children.
+    Intersection,
+    /// Inside the first child but not the second.
+    Difference,
+}
+
+impl CsgOperation {
+    #[inline]
+    fn inside(&self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            Self::Union => in_a || in_b,
+            Self::Intersection => in_a && in_b,
+            Self::Difference => in_a && !in_b,
+        }
+    }
+}
+
+/// Boolean combination of two closed hittables. Each child's surface is found at every
+/// point where the ray enters or leaves it, and a crossing becomes a hit on the result
+/// when it changes whether the ray is inside the combination. The hit keeps the normal,
+/// texture coordinates and material of the child surface it lies on.
+///
+/// Children must be closed with outward-facing normals, so that `front_face` tells
+/// entering from leaving: spheres, boxes from `build_box`, consistently wound meshes,
+/// or other CSG nodes.
+#[derive(Clone, Debug)]
+pub struct Csg {
+    a: Arc<dyn Hittable>,
+    b: Arc<dyn Hittable>,
+    operation: CsgOperation,
+    bbox: AABB,
+}
+
+impl Csg {
+    pub fn new(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>, operation: CsgOperation) -> Self {
+        let (box_a, box_b) = (a.bounding_box(), b.bounding_box());
+        let bbox = match operation {
+            CsgOperation::Union => AABB::from((box_a, box_b)),
+            CsgOperation::Intersection => {
+                let overlap =
+                    |x: Interval, y: Interval| Interval::new(x.min.max(y.min), x.max.min(y.max));
+                let (x, y, z) = (
+                    overlap(box_a.x, box_b.x),
+                    overlap(box_a.y, box_b.y),
+                    overlap(box_a.z, box_b.z),
+                );
+                if x.min > x.max || y.min > y.max || z.min > z.max {
+                    EMPTY
+                } else {
+                    AABB::new(x, y, z)
+                }
+            }
+            CsgOperation::Difference => box_a,
+        };
+
+        Self {
+            a,
+            b,
+            operation,
+            bbox,
+        }
+    }
+
+    pub fn union(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self::new(a, b, CsgOperation::Union)
+    }
+
+    pub fn intersection(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self::new(a, b, CsgOperation::Intersection)
+    }
+
+    pub fn difference(a: Arc<dyn Hittable>, b: Arc<dyn Hittable>) -> Self {
+        Self::new(a, b, CsgOperation::Difference)
+    }
+}
+
+/// Every surface crossing of `object` along `r` from `ray_t.min` on, in order, up to and
+/// including the first one past `ray_t.max`: that one still tells whether the ray ends
+/// the interval inside, and the first crossing whether it starts inside.
+fn crossings(object: &dyn Hittable, r: &Ray, ray_t: &Interval) -> Vec<HitRecord> {
+    let mut hits = Vec::new();
+    let mut t_min = ray_t.min;
+    while let Some(rec) = object.hit(r, &mut Interval::new(t_min, f64::INFINITY)) {
+        // Just past this hit, since some shapes accept hits at `min` itself.
+        t_min = rec.t + 1e-9 * rec.t.abs().max(1.0);
+        let beyond = rec.t > ray_t.max;
+        hits.push(rec);
+        if beyond {
+            break;
+        }
+    }
+    hits
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let hits_a = crossings(self.a.as_ref(), r, ray_t);
+        if hits_a.is_empty() && self.operation != CsgOperation::Union {
+            return None;
+        }
+        let hits_b = crossings(self.b.as_ref(), r, ray_t);
+
+        // A child starts out inside when its first crossing leaves it.
+        let mut in_a = hits_a.first().is_some_and(|rec| !rec.front_face);
+        let mut in_b = hits_b.first().is_some_and(|rec| !rec.front_face);
+        let mut inside = self.operation.inside(in_a, in_b);
+
+        let (mut next_a, mut next_b) =
+            (hits_a.into_iter().peekable(), hits_b.into_iter().peekable());
+        loop {
+            let from_a = match (next_a.peek(), next_b.peek()) {
+                (Some(a), Some(b)) => a.t <= b.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return None,
+            };
+            let mut rec = if from_a {
+                let rec = next_a.next()?;
+                in_a = rec.front_face;
+                rec
+            } else {
+                let rec = next_b.next()?;
+                in_b = rec.front_face;
+                rec
+            };
+            if rec.t > ray_t.max {
+                return None;
+            }
+
+            let now_inside = self.operation.inside(in_a, in_b);
+            if now_inside != inside && ray_t.surrounds(rec.t) {
+                // The normal already faces the ray; only which side is the inside of the
+                // result can differ from the child's, as on the walls carved out by a
+                // difference.
+                rec.front_face = now_inside;
+                return Some(rec);
+            }
+            inside = now_inside;
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+unsafe impl Send for Csg {}
+unsafe impl Sync for Csg {}
@@ -0,0 +1,143 @@
+use std::fmt::Debug;
+
+use crate::{perlin::Perlin, vec3::Point3};
+
+/// Spatially varying extinction density for heterogeneous media.
+pub trait DensityField: Debug {
+    fn density(&self, p: &Point3) -> f64;
+
+    /// Upper bound on `density` anywhere, used as the tracking majorant.
+    fn max_density(&self) -> f64;
+}
+
+/// Voxel grid spanning the box from `min` to `max`, trilinearly interpolated and zero
+/// outside. Voxel values sit at cell centres and are stored x fastest, then y, then z.
+#[derive(Clone, Debug)]
+pub struct DensityGrid {
+    min: Point3,
+    max: Point3,
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    data: Vec<f64>,
+    max_density: f64,
+}
+
+impl DensityGrid {
+    pub fn new(
+        min: Point3,
+        max: Point3,
+        (nx, ny, nz): (usize, usize, usize),
+        data: Vec<f64>,
+    ) -> Self {
+        assert_eq!(data.len(), nx * ny * nz, "Density grid size mismatch");
+        let max_density = data.iter().cloned().fold(0.0, f64::max);
+        Self {
+            min,
+            max,
+            nx,
+            ny,
+            nz,
+            data,
+            max_density,
+        }
+    }
+
+    /// Fills the grid by evaluating `f` at every voxel centre.
+    pub fn from_fn(
+        min: Point3,
+        max: Point3,
+        (nx, ny, nz): (usize, usize, usize),
+        f: impl Fn(Point3) -> f64,
+    ) -> Self {
+        let extent = max - min;
+        let mut data = Vec::with_capacity(nx * ny * nz);
+        for k in 0..nz {
+            for j in 0..ny {
+                for i in 0..nx {
+                    let p = Point3::new(
+                        min.x + extent.x * (i as f64 + 0.5) / nx as f64,
+                        min.y + extent.y * (j as f64 + 0.5) / ny as f64,
+                        min.z + extent.z * (k as f64 + 0.5) / nz as f64,
+                    );
+                    data.push(f(p).max(0.0));
+                }
+            }
+        }
+        Self::new(min, max, (nx, ny, nz), data)
+    }
+
+    #[inline]
+    fn voxel(&self, i: usize, j: usize, k: usize) -> f64 {
+        self.data[(k * self.ny + j) * self.nx + i]
+    }
+}
+
+impl DensityField for DensityGrid {
+    fn density(&self, p: &Point3) -> f64 {
+        let extent = self.max - self.min;
+        let local = *p - self.min;
+        if (0..3).any(|a| local[a] < 0.0 || local[a] > extent[a]) {
+            return 0.0;
+        }
+
+        // Continuous voxel coordinates, clamped so edge cells extend to the boundary.
+        let coord = |x: f64, size: f64, n: usize| {
+            let c = (x / size * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let i = (c.floor() as usize).min(n.saturating_sub(2));
+            (i, (i + 1).min(n - 1), c - i as f64)
+        };
+        let (i0, i1, tx) = coord(local.x, extent.x, self.nx);
+        let (j0, j1, ty) = coord(local.y, extent.y, self.ny);
+        let (k0, k1, tz) = coord(local.z, extent.z, self.nz);
+
+        let lerp = |a: f64, b: f64, t: f64| a + t * (b - a);
+        let x00 = lerp(self.voxel(i0, j0, k0), self.voxel(i1, j0, k0), tx);
+        let x10 = lerp(self.voxel(i0, j1, k0), self.voxel(i1, j1, k0), tx);
+        let x01 = lerp(self.voxel(i0, j0, k1), self.voxel(i1, j0, k1), tx);
+        let x11 = lerp(self.voxel(i0, j1, k1), self.voxel(i1, j1, k1), tx);
+        lerp(lerp(x00, x10, ty), lerp(x01, x11, ty), tz)
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_density
+    }
+}
+
+unsafe impl Send for DensityGrid {}
+unsafe impl Sync for DensityGrid {}
+
+/// Procedural density `density * turb(scale * p)`, clamped to its nominal maximum.
+#[derive(Clone, Debug)]
+pub struct NoiseDensity {
+    noise: Perlin,
+    scale: f64,
+    density: f64,
+    octaves: i32,
+}
+
+impl NoiseDensity {
+    pub fn new(scale: f64, density: f64, octaves: i32) -> Self {
+        Self {
+            noise: Perlin::new(),
+            scale,
+            density,
+            octaves,
+        }
+    }
+}
+
+impl DensityField for NoiseDensity {
+    fn density(&self, p: &Point3) -> f64 {
+        let turb = self.noise.turb(self.scale * *p, self.octaves);
+        (self.density * turb).min(self.max_density())
+    }
+
+    fn max_density(&self) -> f64 {
+        // Octave weights halve each time, and each octave stays within [-1, 1].
+        self.density * (2.0 - 2f64.powi(1 - self.octaves))
+    }
+}
+
+unsafe impl Send for NoiseDensity {}
+unsafe impl Sync for NoiseDensity {}
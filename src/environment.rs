@@ -0,0 +1,163 @@
+use std::{f64::consts::PI, fmt::Debug};
+
+use crate::{
+    color::{luminance, Color},
+    image::Image,
+    utils::{degrees_to_radians, random_double},
+    vec3::Vec3,
+};
+
+/// Radiance arriving from infinitely far away, seen by rays that leave the scene.
+pub trait Environment: Debug {
+    fn radiance(&self, direction: &Vec3) -> Color;
+
+    /// Picks a unit direction towards the environment, returning it with its solid angle pdf.
+    fn sample(&self) -> (Vec3, f64);
+
+    fn pdf(&self, direction: &Vec3) -> f64;
+}
+
+/// Equirectangular (lat-long) environment image, +Y up, with the image centre looking
+/// down -Z. `rotation` turns it about +Y in degrees.
+#[derive(Clone, Debug)]
+pub struct EnvironmentMap {
+    image: Image,
+    intensity: f64,
+    sin_rot: f64,
+    cos_rot: f64,
+    // Row marginal and per-row conditional CDFs over luminance * sin(theta).
+    marginal: Vec<f64>,
+    conditional: Vec<Vec<f64>>,
+    total: f64,
+}
+
+impl EnvironmentMap {
+    pub fn new(image: Image, rotation: f64, intensity: f64) -> Self {
+        let (w, h) = (image.width, image.height);
+
+        let conditional: Vec<Vec<f64>> = (0..h)
+            .map(|y| {
+                let sin_theta = (PI * (y as f64 + 0.5) / h as f64).sin();
+                let mut acc = 0.0;
+                let mut cdf = Vec::with_capacity(w + 1);
+                cdf.push(0.0);
+                for x in 0..w {
+                    acc += luminance(&image.pixel(x, y)).max(0.0) * sin_theta;
+                    cdf.push(acc);
+                }
+                cdf
+            })
+            .collect();
+
+        let mut acc = 0.0;
+        let mut marginal = Vec::with_capacity(h + 1);
+        marginal.push(0.0);
+        for row in &conditional {
+            acc += row[w];
+            marginal.push(acc);
+        }
+
+        let radians = degrees_to_radians(rotation);
+        Self {
+            image,
+            intensity,
+            sin_rot: radians.sin(),
+            cos_rot: radians.cos(),
+            marginal,
+            conditional,
+            total: acc,
+        }
+    }
+
+    fn rotate(&self, d: &Vec3, sin_rot: f64) -> Vec3 {
+        Vec3::new(
+            self.cos_rot * d.x + sin_rot * d.z,
+            d.y,
+            -sin_rot * d.x + self.cos_rot * d.z,
+        )
+    }
+
+    fn direction_to_uv(&self, direction: &Vec3) -> (f64, f64) {
+        let d = self.rotate(&direction.unit_vector(), -self.sin_rot);
+        let u = 0.5 + d.x.atan2(-d.z) / (2.0 * PI);
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+        (u, v)
+    }
+
+    fn texel(&self, u: f64, v: f64) -> (usize, usize) {
+        let x = ((u * self.image.width as f64) as usize).min(self.image.width - 1);
+        let y = ((v * self.image.height as f64) as usize).min(self.image.height - 1);
+        (x, y)
+    }
+
+    /// Finds the segment of `cdf` containing `target`, with the fractional position in it.
+    fn invert_cdf(cdf: &[f64], target: f64) -> (usize, f64) {
+        let n = cdf.len() - 1;
+        let i = cdf
+            .partition_point(|&c| c <= target)
+            .saturating_sub(1)
+            .min(n - 1);
+        let span = cdf[i + 1] - cdf[i];
+        let frac = if span > 0.0 {
+            (target - cdf[i]) / span
+        } else {
+            0.5
+        };
+        (i, frac)
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let (u, v) = self.direction_to_uv(direction);
+        let (x, y) = self.texel(u, v);
+        self.intensity * self.image.pixel(x, y)
+    }
+
+    fn sample(&self) -> (Vec3, f64) {
+        if self.total <= 0.0 {
+            let d = Vec3::random_unit_vector();
+            return (d, 1.0 / (4.0 * PI));
+        }
+
+        let (y, fy) = Self::invert_cdf(&self.marginal, random_double() * self.total);
+        let row = &self.conditional[y];
+        let (x, fx) = Self::invert_cdf(row, random_double() * row[self.image.width]);
+
+        let u = (x as f64 + fx) / self.image.width as f64;
+        let v = (y as f64 + fy) / self.image.height as f64;
+        let theta = v * PI;
+        let phi = 2.0 * PI * (u - 0.5);
+        let local = Vec3::new(
+            theta.sin() * phi.sin(),
+            theta.cos(),
+            -theta.sin() * phi.cos(),
+        );
+        let direction = self.rotate(&local, self.sin_rot);
+
+        (direction, self.pdf(&direction))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        if self.total <= 0.0 {
+            return 1.0 / (4.0 * PI);
+        }
+
+        let (u, v) = self.direction_to_uv(direction);
+        let (x, y) = self.texel(u, v);
+        // The density is constant over a texel in (u, v); the Jacobian to solid angle
+        // takes sin(theta) of the direction itself, not of the texel center.
+        let sin_theta = (PI * v).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        let row = &self.conditional[y];
+        let texel_weight = row[x + 1] - row[x];
+        let pixel_count = (self.image.width * self.image.height) as f64;
+        texel_weight / self.total * pixel_count / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+unsafe impl Send for EnvironmentMap {}
+unsafe impl Sync for EnvironmentMap {}
@@ -0,0 +1,296 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::AABB,
+    color::luminance,
+    image::Image,
+    interval::Interval,
+    material::Material,
+    primitive::{intersect_triangle, uv_partials, HitRecord, Hittable},
+    ray::Ray,
+    texture::{Footprint, Texture},
+    vec3::{Point3, Vec3},
+};
+
+/// Terrain over a regular grid of height samples, spanning `size.x` by `size.z` from the
+/// corner `origin`, with heights scaled by `size.y`. Rays walk the grid cell by cell,
+/// skip cells whose height range they pass over or under, and hit the two triangles of
+/// each cell with smoothly interpolated normals.
+///
+/// Texture coordinates span the whole field like a map seen from above with -z at the
+/// top: `u` runs along +x and `v` along -z, so an image drapes over the heights built
+/// from it the right way round.
+#[derive(Clone, Debug)]
+pub struct Heightfield {
+    origin: Point3,
+    columns: usize,
+    rows: usize,
+    cell_width: f64,
+    cell_depth: f64,
+    points: Vec<Point3>,
+    normals: Vec<Vec3>,
+    /// Lowest and highest point of each cell.
+    cell_bounds: Vec<(f64, f64)>,
+    mat: Arc<Material>,
+    bbox: AABB,
+}
+
+impl Heightfield {
+    /// `heights` holds `columns` samples along x for each of `rows` rows along z,
+    /// starting at the -z edge.
+    pub fn new(
+        origin: Point3,
+        size: Vec3,
+        columns: usize,
+        rows: usize,
+        heights: &[f64],
+        mat: Arc<Material>,
+    ) -> Self {
+        assert!(
+            columns >= 2 && rows >= 2 && heights.len() == columns * rows,
+            "Heightfield needs at least 2x2 samples, one height per sample"
+        );
+        let cell_width = size.x / (columns - 1) as f64;
+        let cell_depth = size.z / (rows - 1) as f64;
+
+        let points: Vec<Point3> = heights
+            .iter()
+            .enumerate()
+            .map(|(k, h)| {
+                let (i, j) = (k % columns, k / columns);
+                origin + Vec3::new(i as f64 * cell_width, h * size.y, j as f64 * cell_depth)
+            })
+            .collect();
+
+        // Smooth normals from central differences, one-sided along the edges.
+        let point = |i: usize, j: usize| points[j * columns + i];
+        let normals = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                let dx = point((i + 1).min(columns - 1), j) - point(i.saturating_sub(1), j);
+                let dz = point(i, (j + 1).min(rows - 1)) - point(i, j.saturating_sub(1));
+                dz.cross(&dx).unit_vector()
+            })
+            .collect();
+
+        let cell_bounds = (0..rows - 1)
+            .flat_map(|j| (0..columns - 1).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                [(i, j), (i + 1, j), (i, j + 1), (i + 1, j + 1)]
+                    .iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(i, j)| {
+                        let y = point(i, j).y;
+                        (lo.min(y), hi.max(y))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let (low, high) = cell_bounds
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(l, h)| {
+                (lo.min(l), hi.max(h))
+            });
+        let bbox = AABB::from((
+            Point3::new(origin.x, low, origin.z),
+            Point3::new(origin.x + size.x, high, origin.z + size.z),
+        ));
+
+        Self {
+            origin,
+            columns,
+            rows,
+            cell_width,
+            cell_depth,
+            points,
+            normals,
+            cell_bounds,
+            mat,
+            bbox,
+        }
+    }
+
+    /// One sample per pixel from the image's luminance, its top row at the -z edge. A
+    /// single row or column of pixels is repeated to make the two the grid needs.
+    pub fn from_image(origin: Point3, size: Vec3, image: &Image, mat: Arc<Material>) -> Self {
+        assert!(
+            image.width > 0 && image.height > 0,
+            "Heightfield needs an image with at least one pixel"
+        );
+        let columns = image.width.max(2);
+        let rows = image.height.max(2);
+        let heights: Vec<f64> = (0..rows)
+            .flat_map(|y| (0..columns).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let (x, y) = (x.min(image.width - 1), y.min(image.height - 1));
+                luminance(&image.pixel(x, y))
+            })
+            .collect();
+        Self::new(origin, size, columns, rows, &heights, mat)
+    }
+
+    /// Samples the luminance of `height` on a `columns` by `rows` grid, at the field's
+    /// texture coordinates and flat positions.
+    pub fn from_texture(
+        origin: Point3,
+        size: Vec3,
+        columns: usize,
+        rows: usize,
+        height: &dyn Texture,
+        mat: Arc<Material>,
+    ) -> Self {
+        let columns = columns.max(2);
+        let rows = rows.max(2);
+        let heights: Vec<f64> = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                let (s, t) = (
+                    i as f64 / (columns - 1) as f64,
+                    j as f64 / (rows - 1) as f64,
+                );
+                let p = origin + Vec3::new(s * size.x, 0.0, t * size.z);
+                luminance(&height.value(s, 1.0 - t, &p))
+            })
+            .collect();
+        Self::new(origin, size, columns, rows, &heights, mat)
+    }
+
+    #[inline]
+    fn index(&self, i: usize, j: usize) -> usize {
+        j * self.columns + i
+    }
+
+    #[inline]
+    fn uv(&self, i: usize, j: usize) -> (f64, f64) {
+        (
+            i as f64 / (self.columns - 1) as f64,
+            1.0 - j as f64 / (self.rows - 1) as f64,
+        )
+    }
+
+    /// Closest hit with one of the two triangles of cell (`i`, `j`).
+    fn hit_cell(&self, r: &Ray, ray_t: &Interval, i: usize, j: usize) -> Option<HitRecord> {
+        let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        [[0, 1, 2], [0, 2, 3]]
+            .into_iter()
+            .filter_map(|triangle| {
+                let grid = triangle.map(|c| corners[c]);
+                let vertices = grid.map(|(i, j)| self.points[self.index(i, j)]);
+                let (t, b1, b2) = intersect_triangle(r, &vertices)?;
+                ray_t
+                    .surrounds(t)
+                    .then(|| self.hit_record(r, t, grid, vertices, b1, b2))
+            })
+            .min_by(|a, b| a.t.total_cmp(&b.t))
+    }
+
+    fn hit_record(
+        &self,
+        r: &Ray,
+        t: f64,
+        grid: [(usize, usize); 3],
+        vertices: [Point3; 3],
+        b1: f64,
+        b2: f64,
+    ) -> HitRecord {
+        let b0 = 1.0 - b1 - b2;
+        let normals = grid.map(|(i, j)| self.normals[self.index(i, j)]);
+        let uvs = grid.map(|(i, j)| self.uv(i, j));
+
+        // The grid winds clockwise seen from above, so the geometric normal is flipped.
+        let (e1, e2) = (vertices[1] - vertices[0], vertices[2] - vertices[0]);
+        let geometric = e2.cross(&e1).unit_vector();
+        let front_face = r.direction().dot(&geometric) < 0.0;
+        let sign = if front_face { 1.0 } else { -1.0 };
+        let shading = (b0 * normals[0] + b1 * normals[1] + b2 * normals[2]).unit_vector();
+
+        let (dpdu, dpdv) = uv_partials(uvs, vertices).unwrap_or((e1, e2));
+        let (dndu, dndv) = uv_partials(uvs, normals).unwrap_or((Vec3::default(), Vec3::default()));
+
+        HitRecord {
+            t,
+            p: b0 * vertices[0] + b1 * vertices[1] + b2 * vertices[2],
+            normal: sign * shading,
+            mat: self.mat.clone(),
+            front_face,
+            u: b0 * uvs[0].0 + b1 * uvs[1].0 + b2 * uvs[2].0,
+            v: b0 * uvs[0].1 + b1 * uvs[1].1 + b2 * uvs[2].1,
+            dpdu,
+            dpdv,
+            dndu: sign * dndu,
+            dndv: sign * dndv,
+            footprint: Footprint::default(),
+            object: None,
+        }
+    }
+}
+
+impl Hittable for Heightfield {
+    /// Walks the cells under the ray in order with a 2D DDA, so the first cell with a
+    /// hit holds the closest one.
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let (t_enter, t_exit) = self.bbox.clip(r)?;
+        let t_start = t_enter.max(ray_t.min);
+        let t_end = t_exit.min(ray_t.max);
+        if t_start > t_end {
+            return None;
+        }
+
+        let (o, d) = (r.origin(), r.direction());
+        let (cells_x, cells_z) = (self.columns - 1, self.rows - 1);
+        let start = r.at(t_start);
+        let cell = |x: f64, origin: f64, width: f64, cells: usize| {
+            (((x - origin) / width).floor().max(0.0) as usize).min(cells - 1)
+        };
+        let mut i = cell(start.x, self.origin.x, self.cell_width, cells_x);
+        let mut j = cell(start.z, self.origin.z, self.cell_depth, cells_z);
+
+        // Parameter of the next cell boundary crossed along each axis, and between them.
+        let setup = |d: f64, o: f64, origin: f64, width: f64, index: usize| {
+            if d == 0.0 {
+                return (f64::INFINITY, f64::INFINITY);
+            }
+            let boundary = origin + width * (index + usize::from(d > 0.0)) as f64;
+            ((boundary - o) / d, width / d.abs())
+        };
+        let (mut next_x, delta_x) = setup(d.x, o.x, self.origin.x, self.cell_width, i);
+        let (mut next_z, delta_z) = setup(d.z, o.z, self.origin.z, self.cell_depth, j);
+
+        let mut t_in = t_start;
+        loop {
+            let t_out = next_x.min(next_z).min(t_end);
+            let (y_in, y_out) = (o.y + t_in * d.y, o.y + t_out * d.y);
+            let (low, high) = self.cell_bounds[j * cells_x + i];
+            if y_in.min(y_out) <= high + 1e-9 && y_in.max(y_out) >= low - 1e-9 {
+                if let Some(rec) = self.hit_cell(r, ray_t, i, j) {
+                    return Some(rec);
+                }
+            }
+
+            if t_out >= t_end {
+                return None;
+            }
+            if next_x < next_z {
+                if (d.x > 0.0 && i + 1 >= cells_x) || (d.x < 0.0 && i == 0) {
+                    return None;
+                }
+                i = if d.x > 0.0 { i + 1 } else { i - 1 };
+                t_in = next_x;
+                next_x += delta_x;
+            } else {
+                if (d.z > 0.0 && j + 1 >= cells_z) || (d.z < 0.0 && j == 0) {
+                    return None;
+                }
+                j = if d.z > 0.0 { j + 1 } else { j - 1 };
+                t_in = next_z;
+                next_z += delta_z;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+unsafe impl Send for Heightfield {}
+unsafe impl Sync for Heightfield {}
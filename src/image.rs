@@ -0,0 +1,411 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use crate::color::{srgb_to_linear, Color};
+
+/// Linear RGB pixels, stored row by row from the top of the image. The raw 8 and 16 bit
+/// decoders keep the file's encoded values; `load` decodes those from sRGB.
+#[derive(Clone, Debug, Default)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    data: Vec<Color>,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, data: Vec<Color>) -> Self {
+        assert_eq!(data.len(), width * height, "Image size mismatch");
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Loads an image, picking the decoder from the file extension.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::load_with(path.as_ref(), true)
+    }
+
+    /// Loads an image whose 8 and 16-bit values are data rather than color, such as a
+    /// normal or height map, so they are not decoded from sRGB.
+    pub fn load_linear(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::load_with(path.as_ref(), false)
+    }
+
+    /// Loads the alpha channel of an image as a grey image, for opacity masks. Only PNG
+    /// files carry alpha.
+    pub fn load_alpha(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("png") => Self::alpha_from_png(&fs::read(path)?),
+            _ => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                format!("No alpha channel in image format: {}", path.display()),
+            )),
+        }
+    }
+
+    fn load_with(path: &Path, srgb: bool) -> io::Result<Self> {
+        let decode = |image: Self| if srgb { image.decode_srgb() } else { image };
+        let bytes = fs::read(path)?;
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        match ext.as_deref() {
+            Some("hdr") | Some("pic") => Self::from_hdr(&bytes),
+            Some("pfm") => Self::from_pfm(&bytes),
+            Some("ppm") | Some("pnm") => Self::from_ppm(&bytes).map(decode),
+            Some("png") => Self::from_png(&bytes).map(decode),
+            _ => Err(io::Error::new(
+                ErrorKind::Unsupported,
+                format!("Unsupported image format: {}", path.display()),
+            )),
+        }
+    }
+
+    #[inline]
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        self.data[y * self.width + x]
+    }
+
+    /// Radiance RGBE (`.hdr`), flat or with new-style run length encoded scanlines.
+    pub fn from_hdr(bytes: &[u8]) -> io::Result<Self> {
+        let mut pos = 0;
+        let next_line = |pos: &mut usize| -> io::Result<String> {
+            let start = *pos;
+            while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                *pos += 1;
+            }
+            if *pos >= bytes.len() {
+                return Err(invalid("Truncated HDR header"));
+            }
+            *pos += 1;
+            Ok(String::from_utf8_lossy(&bytes[start..*pos - 1]).into_owned())
+        };
+
+        let magic = next_line(&mut pos)?;
+        if !magic.starts_with("#?") {
+            return Err(invalid("Missing HDR signature"));
+        }
+        loop {
+            let line = next_line(&mut pos)?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(invalid("Only RGBE HDR images are supported"));
+                }
+            }
+        }
+
+        let resolution = next_line(&mut pos)?;
+        let fields: Vec<&str> = resolution.split_whitespace().collect();
+        let (height, width, flip) = match fields.as_slice() {
+            ["-Y", h, "+X", w] => (h, w, false),
+            ["+Y", h, "+X", w] => (h, w, true),
+            _ => return Err(invalid("Unsupported HDR orientation")),
+        };
+        let height: usize = height.parse().map_err(|_| invalid("Bad HDR height"))?;
+        let width: usize = width.parse().map_err(|_| invalid("Bad HDR width"))?;
+
+        let mut data = Vec::with_capacity(width * height);
+        let mut scanline = vec![[0u8; 4]; width];
+        for _ in 0..height {
+            pos = Self::read_rgbe_scanline(bytes, pos, &mut scanline)?;
+            data.extend(scanline.iter().map(|&rgbe| Self::rgbe_to_color(rgbe)));
+        }
+
+        let mut image = Self::new(width, height, data);
+        if flip {
+            image.flip_vertical();
+        }
+        Ok(image)
+    }
+
+    fn read_rgbe_scanline(
+        bytes: &[u8],
+        mut pos: usize,
+        scanline: &mut [[u8; 4]],
+    ) -> io::Result<usize> {
+        let width = scanline.len();
+        let truncated = || invalid("Truncated HDR pixel data");
+        let header = bytes.get(pos..pos + 4).ok_or_else(truncated)?;
+
+        let is_rle = (8..0x8000).contains(&width)
+            && header[0] == 2
+            && header[1] == 2
+            && header[2] & 0x80 == 0;
+        if !is_rle {
+            for pixel in scanline.iter_mut() {
+                let rgbe = bytes.get(pos..pos + 4).ok_or_else(truncated)?;
+                pixel.copy_from_slice(rgbe);
+                pos += 4;
+            }
+            return Ok(pos);
+        }
+
+        if ((header[2] as usize) << 8 | header[3] as usize) != width {
+            return Err(invalid("HDR scanline width mismatch"));
+        }
+        pos += 4;
+
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = *bytes.get(pos).ok_or_else(truncated)? as usize;
+                pos += 1;
+                if count > 128 {
+                    let run = count - 128;
+                    let value = *bytes.get(pos).ok_or_else(truncated)?;
+                    pos += 1;
+                    if x + run > width {
+                        return Err(invalid("HDR run overflows scanline"));
+                    }
+                    scanline[x..x + run]
+                        .iter_mut()
+                        .for_each(|p| p[channel] = value);
+                    x += run;
+                } else {
+                    if count == 0 || x + count > width {
+                        return Err(invalid("Bad HDR run length"));
+                    }
+                    let values = bytes.get(pos..pos + count).ok_or_else(truncated)?;
+                    for (p, &value) in scanline[x..x + count].iter_mut().zip(values) {
+                        p[channel] = value;
+                    }
+                    pos += count;
+                    x += count;
+                }
+            }
+        }
+
+        Ok(pos)
+    }
+
+    fn rgbe_to_color([r, g, b, e]: [u8; 4]) -> Color {
+        if e == 0 {
+            return Color::default();
+        }
+        let f = 2f64.powi(e as i32 - 136);
+        Color::new(
+            (r as f64 + 0.5) * f,
+            (g as f64 + 0.5) * f,
+            (b as f64 + 0.5) * f,
+        )
+    }
+
+    /// Portable float map, color (`PF`) or greyscale (`Pf`).
+    pub fn from_pfm(bytes: &[u8]) -> io::Result<Self> {
+        // The header is three whitespace separated tokens after the magic, ended by a
+        // single whitespace byte.
+        let mut tokens = Vec::with_capacity(4);
+        let mut pos = 0;
+        while tokens.len() < 4 {
+            while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            let start = pos;
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(invalid("Truncated PFM header"));
+            }
+            tokens.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+        }
+        pos += 1;
+
+        let channels = match tokens[0].as_str() {
+            "PF" => 3,
+            "Pf" => 1,
+            _ => return Err(invalid("Missing PFM signature")),
+        };
+        let width: usize = tokens[1].parse().map_err(|_| invalid("Bad PFM width"))?;
+        let height: usize = tokens[2].parse().map_err(|_| invalid("Bad PFM height"))?;
+        let scale: f64 = tokens[3].parse().map_err(|_| invalid("Bad PFM scale"))?;
+        let little_endian = scale < 0.0;
+
+        let floats = bytes
+            .get(pos..pos + width * height * channels * 4)
+            .ok_or_else(|| invalid("Truncated PFM pixel data"))?
+            .chunks_exact(4)
+            .map(|b| {
+                let b = [b[0], b[1], b[2], b[3]];
+                if little_endian {
+                    f32::from_le_bytes(b) as f64
+                } else {
+                    f32::from_be_bytes(b) as f64
+                }
+            })
+            .collect::<Vec<f64>>();
+
+        let data = floats
+            .chunks_exact(channels)
+            .map(|c| match c {
+                [r, g, b] => Color::new(*r, *g, *b),
+                _ => Color::new(c[0], c[0], c[0]),
+            })
+            .collect();
+
+        // PFM rows run from the bottom of the image up.
+        let mut image = Self::new(width, height, data);
+        image.flip_vertical();
+        Ok(image)
+    }
+
+    /// Netpbm pixmap, ASCII (`P3`) or binary (`P6`), scaled to [0, 1].
+    pub fn from_ppm(bytes: &[u8]) -> io::Result<Self> {
+        // Four whitespace separated header tokens, with `#` comments running to the end
+        // of the line, then a single whitespace byte before binary data.
+        let mut pos = 0;
+        let next_token = |pos: &mut usize| -> io::Result<String> {
+            loop {
+                while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+                    *pos += 1;
+                }
+                if *pos < bytes.len() && bytes[*pos] == b'#' {
+                    while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                        *pos += 1;
+                    }
+                } else {
+                    break;
+                }
+            }
+            let start = *pos;
+            while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+                *pos += 1;
+            }
+            if start == *pos {
+                return Err(invalid("Truncated PPM file"));
+            }
+            Ok(String::from_utf8_lossy(&bytes[start..*pos]).into_owned())
+        };
+
+        let magic = next_token(&mut pos)?;
+        let width: usize = next_token(&mut pos)?
+            .parse()
+            .map_err(|_| invalid("Bad PPM width"))?;
+        let height: usize = next_token(&mut pos)?
+            .parse()
+            .map_err(|_| invalid("Bad PPM height"))?;
+        let max_value: u32 = next_token(&mut pos)?
+            .parse()
+            .map_err(|_| invalid("Bad PPM maximum value"))?;
+        if !(1..=65535).contains(&max_value) {
+            return Err(invalid("Bad PPM maximum value"));
+        }
+        let count = width * height * 3;
+
+        let samples: Vec<u32> = match magic.as_str() {
+            "P3" => (0..count)
+                .map(|_| {
+                    next_token(&mut pos)?
+                        .parse()
+                        .map_err(|_| invalid("Bad PPM sample"))
+                })
+                .collect::<io::Result<_>>()?,
+            "P6" => {
+                pos += 1;
+                let sample_size = if max_value < 256 { 1 } else { 2 };
+                bytes
+                    .get(pos..pos + count * sample_size)
+                    .ok_or_else(|| invalid("Truncated PPM pixel data"))?
+                    .chunks_exact(sample_size)
+                    .map(|b| match b {
+                        [v] => *v as u32,
+                        _ => u16::from_be_bytes([b[0], b[1]]) as u32,
+                    })
+                    .collect()
+            }
+            _ => return Err(invalid("Missing PPM signature")),
+        };
+
+        let scale = 1.0 / max_value as f64;
+        let data = samples
+            .chunks_exact(3)
+            .map(|c| Color::new(c[0] as f64, c[1] as f64, c[2] as f64) * scale)
+            .collect();
+        Ok(Self::new(width, height, data))
+    }
+
+    /// PNG of any bit depth and color type, scaled to [0, 1]. Alpha is left to
+    /// `alpha_from_png`.
+    pub fn from_png(bytes: &[u8]) -> io::Result<Self> {
+        let (width, height, channels, samples) = Self::decode_png(bytes)?;
+        let data = samples
+            .chunks_exact(channels)
+            .take(width * height)
+            .map(|c| match c {
+                [grey] | [grey, _] => Color::new(*grey, *grey, *grey),
+                _ => Color::new(c[0], c[1], c[2]),
+            })
+            .collect();
+        Ok(Self::new(width, height, data))
+    }
+
+    /// The alpha channel of a PNG as a grey image, opaque white if it has none.
+    pub fn alpha_from_png(bytes: &[u8]) -> io::Result<Self> {
+        let (width, height, channels, samples) = Self::decode_png(bytes)?;
+        let data = samples
+            .chunks_exact(channels)
+            .take(width * height)
+            .map(|c| match c {
+                [_, alpha] | [_, _, _, alpha] => Color::new(*alpha, *alpha, *alpha),
+                _ => Color::new(1.0, 1.0, 1.0),
+            })
+            .collect();
+        Ok(Self::new(width, height, data))
+    }
+
+    /// Width, height, channel count and interleaved samples scaled to [0, 1].
+    fn decode_png(bytes: &[u8]) -> io::Result<(usize, usize, usize, Vec<f64>)> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+        let (width, height) = (info.width as usize, info.height as usize);
+
+        let samples = match info.bit_depth {
+            png::BitDepth::Sixteen => buf[..info.buffer_size()]
+                .chunks_exact(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]) as f64 / 65535.0)
+                .collect(),
+            _ => buf[..info.buffer_size()]
+                .iter()
+                .map(|&b| b as f64 / 255.0)
+                .collect(),
+        };
+
+        Ok((width, height, info.color_type.samples(), samples))
+    }
+
+    /// Converts sRGB encoded values to linear ones.
+    pub fn decode_srgb(mut self) -> Self {
+        for c in self.data.iter_mut() {
+            *c = Color::new(
+                srgb_to_linear(c.x),
+                srgb_to_linear(c.y),
+                srgb_to_linear(c.z),
+            );
+        }
+        self
+    }
+
+    fn flip_vertical(&mut self) {
+        let width = self.width;
+        let rows: Vec<Vec<Color>> = self.data.chunks(width).rev().map(|r| r.to_vec()).collect();
+        self.data = rows.concat();
+    }
+}
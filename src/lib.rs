@@ -2,11 +2,21 @@ pub mod aabb;
 pub mod bvh;
 pub mod camera;
 pub mod color;
+pub mod csg;
+pub mod density;
+pub mod environment;
+pub mod heightfield;
+pub mod image;
 pub mod interval;
+pub mod light;
 pub mod material;
+pub mod onb;
 pub mod perlin;
 pub mod primitive;
 pub mod ray;
+pub mod sdf;
+pub mod sky;
 pub mod texture;
 pub mod utils;
 pub mod vec3;
+pub mod worley;
@@ -0,0 +1,148 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    onb::ONB,
+    utils::{degrees_to_radians, random_double},
+    vec3::{Point3, Vec3},
+};
+
+/// Scene lights that are sampled directly rather than hit by rays.
+///
+/// Angles are in degrees. `falloff` is the distance exponent, 2.0 being the physical
+/// inverse-square law. `direction` is the way the light travels, not towards the light.
+#[derive(Clone, Copy, Debug)]
+pub enum Light {
+    Point {
+        position: Point3,
+        intensity: Color,
+        falloff: f64,
+    },
+    Spot {
+        position: Point3,
+        direction: Vec3,
+        intensity: Color,
+        inner_angle: f64,
+        outer_angle: f64,
+        falloff: f64,
+    },
+    Directional {
+        direction: Vec3,
+        irradiance: Color,
+        angular_radius: f64,
+    },
+}
+
+/// Direction from the shaded point towards the light, the distance to it and the
+/// incident radiance already divided by the sampling pdf.
+#[derive(Clone, Copy, Debug)]
+pub struct LightSample {
+    pub direction: Vec3,
+    pub distance: f64,
+    pub radiance: Color,
+}
+
+impl Light {
+    pub fn sample(&self, p: Point3) -> Option<LightSample> {
+        match self {
+            Self::Point {
+                position,
+                intensity,
+                falloff,
+            } => {
+                let to_light = *position - p;
+                let distance = to_light.length();
+
+                Some(LightSample {
+                    direction: to_light / distance,
+                    distance,
+                    radiance: *intensity / distance.powf(*falloff),
+                })
+            }
+            Self::Spot {
+                position,
+                direction,
+                intensity,
+                inner_angle,
+                outer_angle,
+                falloff,
+            } => {
+                let to_light = *position - p;
+                let distance = to_light.length();
+                let wi = to_light / distance;
+
+                let cos_theta = (-wi).dot(&direction.unit_vector());
+                let cos_outer = degrees_to_radians(*outer_angle).cos();
+                let cos_inner = degrees_to_radians(*inner_angle).cos();
+                if cos_theta <= cos_outer {
+                    return None;
+                }
+                let cone = if cos_theta >= cos_inner {
+                    1.0
+                } else {
+                    let t = (cos_theta - cos_outer) / (cos_inner - cos_outer);
+                    t * t * (3.0 - 2.0 * t)
+                };
+
+                Some(LightSample {
+                    direction: wi,
+                    distance,
+                    radiance: cone * *intensity / distance.powf(*falloff),
+                })
+            }
+            Self::Directional {
+                direction,
+                irradiance,
+                angular_radius,
+            } => {
+                let axis = -direction.unit_vector();
+                if *angular_radius <= 0.0 {
+                    return Some(LightSample {
+                        direction: axis,
+                        distance: f64::INFINITY,
+                        radiance: *irradiance,
+                    });
+                }
+
+                // Uniform over the disk's cone: radiance / pdf = radiance * solid angle.
+                let cos_max = degrees_to_radians(*angular_radius).cos();
+                let cos_theta = 1.0 - random_double() * (1.0 - cos_max);
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                let phi = 2.0 * PI * random_double();
+                let local = Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+                let solid_angle = 2.0 * PI * (1.0 - cos_max);
+
+                Some(LightSample {
+                    direction: ONB::new(&axis).transform(local),
+                    distance: f64::INFINITY,
+                    radiance: self.radiance(&axis) * solid_angle,
+                })
+            }
+        }
+    }
+
+    /// Radiance seen along `direction` by a ray that escapes the scene. Only a
+    /// directional light with a visible disk has any.
+    pub fn radiance(&self, direction: &Vec3) -> Color {
+        match self {
+            Self::Directional {
+                direction: light_direction,
+                irradiance,
+                angular_radius,
+            } if *angular_radius > 0.0 => {
+                let sin_max = degrees_to_radians(*angular_radius).sin();
+                let cos_max = degrees_to_radians(*angular_radius).cos();
+                let cos_theta = direction.unit_vector().dot(&-light_direction.unit_vector());
+                if cos_theta >= cos_max {
+                    *irradiance / (PI * sin_max * sin_max)
+                } else {
+                    Color::default()
+                }
+            }
+            _ => Color::default(),
+        }
+    }
+}
+
+unsafe impl Send for Light {}
+unsafe impl Sync for Light {}
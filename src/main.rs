@@ -1,14 +1,34 @@
 // use std::rc::Rc;
+use std::f64::consts::PI;
 use std::sync::Arc;
 
+use raytracing::aabb::AABB;
 use raytracing::bvh::BVHNode;
 use raytracing::camera::Camera;
 use raytracing::color::Color;
-use raytracing::material::Material;
+use raytracing::csg::Csg;
+use raytracing::density::{DensityGrid, NoiseDensity};
+use raytracing::environment::EnvironmentMap;
+use raytracing::heightfield::Heightfield;
+use raytracing::image::Image;
+use raytracing::light::Light;
+use raytracing::material::{
+    EmissionProfile, Material, MediumCoefficients, PhaseFunction, ThinFilm,
+};
+use raytracing::perlin::{Fractal, Perlin};
 use raytracing::primitive::{
-    build_box, ConstantMedium, HittableList, Planar, RotateY, Shape, Sphere, Translate,
+    build_box, build_displaced_mesh, build_displaced_quad, AlphaMasked, AlphaMode, Bumped, Cone,
+    ConstantMedium, Cylinder, Disk, HeterogeneousMedium, HittableList, Planar, RotateY, Shape,
+    Sphere, SurfaceDetail, Torus, Translate, Triangle,
+};
+use raytracing::sdf::{Sdf, SdfObject};
+use raytracing::sky::PreethamSky;
+use raytracing::texture::{
+    AddTexture, Channel, ChannelTexture, CheckerTexture, ClampTexture, ColorRamp, CombineTexture,
+    ImageTexture, InvertTexture, MixTexture, MultiplyTexture, NoisePattern, NoiseTexture,
+    PatternTexture, Projection, ProjectionTexture, RampTexture, RemapTexture, SolidColor, Texture,
+    TransformTexture, UvCheckerTexture, WrapMode,
 };
-use raytracing::texture::{CheckerTexture, NoiseTexture, SolidColor};
 use raytracing::utils::{random_double, random_range};
 use raytracing::vec3::{Point3, Vec3};
 
@@ -57,12 +77,19 @@ fn bouncing_spheres() {
                 // metal
                 let albedo = Color::random_range(0.5, 1.0);
                 let fuzz = random_range(0.0, 0.5);
-                let sphere_material = Arc::new(Material::Metal { albedo, fuzz });
+                let sphere_material = Arc::new(Material::Metal {
+                    albedo,
+                    fuzz,
+                    coating: None,
+                });
                 world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
             } else {
                 // glass
                 let sphere_material = Arc::new(Material::Dielectric {
                     refraction_index: 1.5,
+                    dispersion: 0.0,
+                    priority: 0,
+                    coating: None,
                 });
                 world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
             }
@@ -71,6 +98,9 @@ fn bouncing_spheres() {
 
     let material1 = Arc::new(Material::Dielectric {
         refraction_index: 1.5,
+        dispersion: 0.0,
+        priority: 0,
+        coating: None,
     });
     world.add(Arc::new(Sphere::new(
         Point3::new(0.0, 1.0, 0.0),
@@ -90,6 +120,7 @@ fn bouncing_spheres() {
     let material3 = Arc::new(Material::Metal {
         albedo: Color::new(0.7, 0.6, 0.5),
         fuzz: 0.0,
+        coating: None,
     });
     world.add(Arc::new(Sphere::new(
         Point3::new(4.0, 1.0, 0.0),
@@ -304,6 +335,9 @@ fn simple_light() {
 
     let difflight = Arc::new(Material::DiffuseLight {
         tex: Arc::new(SolidColor::new(&Color::new(4.0, 4.0, 4.0))),
+        scale: 1.0,
+        two_sided: false,
+        profile: EmissionProfile::Diffuse,
     });
     world.add(Arc::new(Sphere::new(
         Point3::new(0.0, 7.0, 0.0),
@@ -351,6 +385,9 @@ fn cornell_box() {
     });
     let light = Arc::new(Material::DiffuseLight {
         tex: Arc::new(SolidColor::new(&Color::new(10., 10., 10.))),
+        scale: 1.0,
+        two_sided: false,
+        profile: EmissionProfile::Diffuse,
     });
 
     world.add(Arc::new(Planar::new(
@@ -446,6 +483,9 @@ fn cornell_smoke() {
     });
     let light = Arc::new(Material::DiffuseLight {
         tex: Arc::new(SolidColor::new(&Color::new(7.0, 7.0, 7.0))),
+        scale: 1.0,
+        two_sided: false,
+        profile: EmissionProfile::Diffuse,
     });
 
     world.add(Arc::new(Planar::new(
@@ -568,6 +608,9 @@ fn final_scene(image_width: i32, samples_per_pixel: i32, max_depth: i32) {
 
     let light = Arc::new(Material::DiffuseLight {
         tex: Arc::new(SolidColor::new(&Color::new(7.0, 7.0, 7.0))),
+        scale: 1.0,
+        two_sided: false,
+        profile: EmissionProfile::Diffuse,
     });
     world.add(Arc::new(Planar::new(
         Point3::new(123.0, 554.0, 147.0),
@@ -594,6 +637,9 @@ fn final_scene(image_width: i32, samples_per_pixel: i32, max_depth: i32) {
         50.0,
         Arc::new(Material::Dielectric {
             refraction_index: 1.5,
+            dispersion: 0.0,
+            priority: 0,
+            coating: None,
         }),
     )));
     world.add(Arc::new(Sphere::new(
@@ -602,6 +648,7 @@ fn final_scene(image_width: i32, samples_per_pixel: i32, max_depth: i32) {
         Arc::new(Material::Metal {
             albedo: Color::new(0.8, 0.8, 0.9),
             fuzz: 1.0,
+            coating: None,
         }),
     )));
 
@@ -610,6 +657,9 @@ fn final_scene(image_width: i32, samples_per_pixel: i32, max_depth: i32) {
         70.0,
         Arc::new(Material::Dielectric {
             refraction_index: 1.5,
+            dispersion: 0.0,
+            priority: 0,
+            coating: None,
         }),
     ));
     world.add(boundary.clone());
@@ -623,6 +673,9 @@ fn final_scene(image_width: i32, samples_per_pixel: i32, max_depth: i32) {
         5000.0,
         Arc::new(Material::Dielectric {
             refraction_index: 1.5,
+            dispersion: 0.0,
+            priority: 0,
+            coating: None,
         }),
     ));
     world.add(Arc::new(ConstantMedium::new(
@@ -677,6 +730,2055 @@ fn final_scene(image_width: i32, samples_per_pixel: i32, max_depth: i32) {
 
     cam.render(Arc::new(world));
 }
+
+fn nested_dielectrics() {
+    let mut world = HittableList::default();
+
+    let checker = Arc::new(CheckerTexture::from((
+        0.5,
+        &Color::new(0.2, 0.3, 0.1),
+        &Color::new(0.9, 0.9, 0.9),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Material::Lambertian { tex: checker }),
+    )));
+
+    // Water pool the glass balls are half submerged in. The glass has the higher
+    // priority, so the overlap is glass and the water/glass interface uses 1.33 -> 1.5.
+    let water = Arc::new(Material::Dielectric {
+        refraction_index: 1.33,
+        dispersion: 0.0,
+        priority: 1,
+        coating: None,
+    });
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -999.0, 0.0),
+        1000.0,
+        water,
+    )));
+
+    let glass = Arc::new(Material::Dielectric {
+        refraction_index: 1.5,
+        dispersion: 0.0,
+        priority: 2,
+        coating: None,
+    });
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1.2, 1.0, 0.0),
+        1.0,
+        glass.clone(),
+    )));
+
+    // Glass ball filled with a murky liquid core.
+    world.add(Arc::new(Sphere::new(
+        Point3::new(1.2, 1.0, 0.0),
+        1.0,
+        glass.clone(),
+    )));
+    let liquid = Arc::new(Sphere::new(
+        Point3::new(1.2, 1.0, 0.0),
+        0.85,
+        Arc::new(Material::Dielectric {
+            refraction_index: 1.33,
+            dispersion: 0.0,
+            priority: 3,
+            coating: None,
+        }),
+    ));
+    world.add(liquid.clone());
+    world.add(Arc::new(ConstantMedium::new(
+        liquid,
+        0.5,
+        Arc::new(SolidColor::new(&Color::new(0.2, 0.4, 0.9))),
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        background: Color::new(0.70, 0.80, 1.00),
+
+        vfov: 30.0,
+        lookfrom: Point3::new(0.0, 3.0, 8.0),
+        lookat: Point3::new(0.0, 0.8, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn scene_lights() {
+    let mut world = HittableList::default();
+
+    let pertext = Arc::new(NoiseTexture::new(4.0));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Material::Lambertian {
+            tex: pertext.clone(),
+        }),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 2.0, 0.0),
+        2.0,
+        Arc::new(Material::Lambertian {
+            tex: pertext.clone(),
+        }),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-3.0, 1.0, 3.0),
+        1.0,
+        Arc::new(Material::Metal {
+            albedo: Color::new(0.8, 0.8, 0.9),
+            fuzz: 0.0,
+            coating: None,
+        }),
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        background: Color::new(0.02, 0.02, 0.04),
+        lights: vec![
+            Light::Point {
+                position: Point3::new(4.0, 5.0, 4.0),
+                intensity: Color::new(60.0, 50.0, 40.0),
+                falloff: 2.0,
+            },
+            Light::Spot {
+                position: Point3::new(-6.0, 8.0, -2.0),
+                direction: Vec3::new(6.0, -6.0, 2.0),
+                intensity: Color::new(40.0, 60.0, 120.0),
+                inner_angle: 10.0,
+                outer_angle: 20.0,
+                falloff: 2.0,
+            },
+            Light::Directional {
+                direction: Vec3::new(-1.0, -1.0, -0.5),
+                irradiance: Color::new(0.6, 0.55, 0.5),
+                angular_radius: 0.5,
+            },
+        ],
+
+        vfov: 20.0,
+        lookfrom: Point3::new(26.0, 3.0, 6.0),
+        lookat: Point3::new(0.0, 2.0, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn environment_map() {
+    let mut path = String::new();
+    eprintln!("Path to lat-long .hdr/.pfm image: ");
+    std::io::stdin()
+        .read_line(&mut path)
+        .expect("Invalid input");
+    let image = Image::load(path.trim()).expect("Could not load environment image");
+
+    let mut world = HittableList::default();
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::new(&Color::new(0.5, 0.5, 0.5))),
+        }),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::new(&Color::new(0.8, 0.3, 0.2))),
+        }),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Dielectric {
+            refraction_index: 1.5,
+            dispersion: 0.0,
+            priority: 0,
+            coating: None,
+        }),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Metal {
+            albedo: Color::new(0.8, 0.8, 0.8),
+            fuzz: 0.05,
+            coating: None,
+        }),
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        environment: Some(Arc::new(EnvironmentMap::new(image, 0.0, 1.0))),
+
+        vfov: 30.0,
+        lookfrom: Point3::new(0.0, 2.5, 9.0),
+        lookat: Point3::new(0.0, 1.0, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn physical_sky() {
+    let mut world = HittableList::default();
+
+    let checker = Arc::new(CheckerTexture::from((
+        1.0,
+        &Color::new(0.2, 0.3, 0.1),
+        &Color::new(0.9, 0.9, 0.9),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Material::Lambertian { tex: checker }),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::new(&Color::new(0.8, 0.8, 0.8))),
+        }),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Metal {
+            albedo: Color::new(0.8, 0.8, 0.8),
+            fuzz: 0.0,
+            coating: None,
+        }),
+    )));
+
+    let sky = PreethamSky::new(
+        Vec3::new(-1.0, 0.35, -0.6),
+        3.0,
+        Color::new(0.3, 0.3, 0.3),
+        0.04,
+    );
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        lights: vec![sky.sun()],
+        environment: Some(Arc::new(sky)),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 2.0, 9.0),
+        lookat: Point3::new(0.0, 2.0, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn cornell_plume() {
+    let mut world = HittableList::default();
+
+    let red = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::new(&Color::new(0.65, 0.05, 0.05))),
+    });
+    let white = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::new(&Color::new(0.73, 0.73, 0.73))),
+    });
+    let green = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::new(&Color::new(0.12, 0.45, 0.15))),
+    });
+    let light = Arc::new(Material::DiffuseLight {
+        tex: Arc::new(SolidColor::new(&Color::new(7.0, 7.0, 7.0))),
+        scale: 1.0,
+        two_sided: false,
+        profile: EmissionProfile::Diffuse,
+    });
+
+    world.add(Arc::new(Planar::new(
+        Point3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 555., 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        green,
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 555., 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        red,
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(113., 554., 127.),
+        Vec3::new(330., 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 305.),
+        light,
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 555., 0.0),
+        Vec3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        white.clone(),
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        white.clone(),
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, 555.),
+        Vec3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 555., 0.0),
+        white.clone(),
+        Shape::Quad,
+    )));
+
+    // Smoke plume rising from the floor: a widening column broken up by turbulence.
+    let noise = Perlin::new();
+    let (min, max) = (
+        Point3::new(130.0, 0.0, 180.0),
+        Point3::new(330.0, 450.0, 380.0),
+    );
+    let plume = DensityGrid::from_fn(min, max, (48, 96, 48), |p| {
+        let height = p.y / 450.0;
+        let radius = 20.0 + 70.0 * height;
+        let offset = Vec3::new(p.x - 230.0, 0.0, p.z - 280.0).length();
+        let falloff = (1.0 - offset / radius).max(0.0);
+        0.2 * falloff * noise.turb(p * 0.02, 5) * (1.0 - height)
+    });
+    world.add(Arc::new(HeterogeneousMedium::new(
+        build_box(min, max, white.clone()),
+        Arc::new(plume),
+        Arc::new(SolidColor::new(&Color::new(0.8, 0.8, 0.8))),
+    )));
+
+    // Cloudy cube driven directly by procedural noise, scattering mostly forward.
+    let cloud = build_box(
+        Point3::new(340.0, 60.0, 120.0),
+        Point3::new(500.0, 220.0, 280.0),
+        white.clone(),
+    );
+    world.add(Arc::new(HeterogeneousMedium::with_phase(
+        cloud,
+        Arc::new(NoiseDensity::new(0.03, 0.1, 4)),
+        Arc::new(SolidColor::new(&Color::new(0.9, 0.9, 0.9))),
+        PhaseFunction::DoubleHenyeyGreenstein {
+            g1: 0.7,
+            g2: -0.3,
+            weight: 0.8,
+        },
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 1.0,
+        image_width: 600,
+        samples_per_pixel: 200,
+        max_depth: 50,
+        background: Color::default(),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(278.0, 278.0, -800.0),
+        lookat: Point3::new(278.0, 278.0, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn cornell_media() {
+    let mut world = HittableList::default();
+
+    let red = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::new(&Color::new(0.65, 0.05, 0.05))),
+    });
+    let white = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::new(&Color::new(0.73, 0.73, 0.73))),
+    });
+    let green = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::new(&Color::new(0.12, 0.45, 0.15))),
+    });
+    let light = Arc::new(Material::DiffuseLight {
+        tex: Arc::new(SolidColor::new(&Color::new(4.0, 4.0, 4.0))),
+        scale: 1.0,
+        two_sided: false,
+        profile: EmissionProfile::Diffuse,
+    });
+
+    world.add(Arc::new(Planar::new(
+        Point3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 555., 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        green,
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 555., 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        red,
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(113., 554., 127.),
+        Vec3::new(330., 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 305.),
+        light,
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 555., 0.0),
+        Vec3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        white.clone(),
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        white.clone(),
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, 555.),
+        Vec3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 555., 0.0),
+        white.clone(),
+        Shape::Quad,
+    )));
+
+    // Block of murky blue liquid: absorbs red strongly, scatters a little.
+    let liquid = build_box(
+        Point3::new(60.0, 0.0, 150.0),
+        Point3::new(230.0, 200.0, 320.0),
+        white.clone(),
+    );
+    world.add(Arc::new(ConstantMedium::with_coefficients(
+        liquid,
+        MediumCoefficients {
+            sigma_a: Color::new(0.02, 0.006, 0.002),
+            sigma_s: Color::new(0.002, 0.002, 0.002),
+            emission: Color::default(),
+            phase: PhaseFunction::HenyeyGreenstein { g: 0.5 },
+        },
+    )));
+
+    // Flame: an emissive, mostly absorbing plume that glows hottest at its base.
+    let noise = Perlin::new();
+    let (min, max) = (
+        Point3::new(300.0, 0.0, 200.0),
+        Point3::new(460.0, 360.0, 360.0),
+    );
+    let flame = DensityGrid::from_fn(min, max, (40, 90, 40), |p| {
+        let height = p.y / 360.0;
+        let radius = 70.0 * (1.0 - height);
+        let offset = Vec3::new(p.x - 380.0, 0.0, p.z - 280.0).length();
+        let falloff = (1.0 - offset / radius.max(1e-3)).max(0.0);
+        falloff * noise.turb(p * 0.03, 4)
+    });
+    world.add(Arc::new(HeterogeneousMedium::with_coefficients(
+        build_box(min, max, white.clone()),
+        Arc::new(flame),
+        MediumCoefficients {
+            sigma_a: Color::new(0.02, 0.02, 0.02),
+            sigma_s: Color::new(0.005, 0.005, 0.005),
+            emission: Color::new(0.4, 0.12, 0.02),
+            phase: PhaseFunction::Isotropic,
+        },
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 1.0,
+        image_width: 600,
+        samples_per_pixel: 200,
+        max_depth: 50,
+        background: Color::default(),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(278.0, 278.0, -800.0),
+        lookat: Point3::new(278.0, 278.0, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn cornell_subsurface() {
+    let mut world = HittableList::default();
+
+    let red = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::new(&Color::new(0.65, 0.05, 0.05))),
+    });
+    let white = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::new(&Color::new(0.73, 0.73, 0.73))),
+    });
+    let green = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::new(&Color::new(0.12, 0.45, 0.15))),
+    });
+    let light = Arc::new(Material::DiffuseLight {
+        tex: Arc::new(SolidColor::new(&Color::new(7.0, 7.0, 7.0))),
+        scale: 1.0,
+        two_sided: false,
+        profile: EmissionProfile::Diffuse,
+    });
+
+    world.add(Arc::new(Planar::new(
+        Point3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 555., 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        green,
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 555., 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        red,
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(113., 554., 127.),
+        Vec3::new(330., 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 305.),
+        light,
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 555., 0.0),
+        Vec3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        white.clone(),
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 555.),
+        white.clone(),
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, 555.),
+        Vec3::new(555., 0.0, 0.0),
+        Vec3::new(0.0, 555., 0.0),
+        white.clone(),
+        Shape::Quad,
+    )));
+
+    // Marble, wax and skin: same shape of walk, different albedo and scattering distance.
+    let marble = Arc::new(Material::Subsurface {
+        albedo: Color::new(0.83, 0.79, 0.75),
+        mean_free_path: Color::new(20.0, 18.0, 15.0),
+        refraction_index: 1.5,
+        phase: PhaseFunction::Isotropic,
+    });
+    let wax = Arc::new(Material::Subsurface {
+        albedo: Color::new(0.95, 0.8, 0.35),
+        mean_free_path: Color::new(40.0, 25.0, 10.0),
+        refraction_index: 1.45,
+        phase: PhaseFunction::HenyeyGreenstein { g: 0.3 },
+    });
+    let skin = Arc::new(Material::Subsurface {
+        albedo: Color::new(0.85, 0.55, 0.45),
+        mean_free_path: Color::new(12.0, 5.0, 3.0),
+        refraction_index: 1.4,
+        phase: PhaseFunction::HenyeyGreenstein { g: 0.8 },
+    });
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(400.0, 90.0, 200.0),
+        90.0,
+        marble,
+    )));
+    let candle = build_box(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(110.0, 260.0, 110.0),
+        wax,
+    );
+    let candle = Arc::new(RotateY::new(candle, 20.0));
+    world.add(Arc::new(Translate::new(
+        candle,
+        Vec3::new(90.0, 0.0, 300.0),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(250.0, 70.0, 120.0),
+        70.0,
+        skin,
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 1.0,
+        image_width: 600,
+        samples_per_pixel: 200,
+        max_depth: 50,
+        background: Color::default(),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(278.0, 278.0, -800.0),
+        lookat: Point3::new(278.0, 278.0, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn thin_films() {
+    let mut world = HittableList::default();
+
+    let checker = Arc::new(CheckerTexture::from((
+        1.0,
+        &Color::new(0.2, 0.3, 0.1),
+        &Color::new(0.9, 0.9, 0.9),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Material::Lambertian { tex: checker }),
+    )));
+
+    // Soap bubbles: a water film with swirling thickness around air, so rays refract
+    // straight through and only the reflection is tinted.
+    let soap = ThinFilm {
+        thickness: 800.0,
+        refraction_index: 1.33,
+        thickness_map: Some(Arc::new(NoiseTexture::new(2.0))),
+    };
+    let bubble = Arc::new(Material::Dielectric {
+        refraction_index: 1.0,
+        dispersion: 0.0,
+        priority: 0,
+        coating: Some(soap),
+    });
+    for (center, radius) in [
+        (Point3::new(-2.0, 1.6, 0.5), 1.0),
+        (Point3::new(-0.4, 2.6, -1.0), 0.7),
+        (Point3::new(0.2, 1.1, 1.5), 0.5),
+    ] {
+        world.add(Arc::new(Sphere::new(center, radius, bubble.clone())));
+    }
+
+    // Heat-tinted steel: an oxide layer over a grey conductor.
+    world.add(Arc::new(Sphere::new(
+        Point3::new(2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Metal {
+            albedo: Color::new(0.6, 0.6, 0.6),
+            fuzz: 0.05,
+            coating: Some(ThinFilm::new(350.0, 2.4)),
+        }),
+    )));
+
+    let sky = PreethamSky::new(
+        Vec3::new(-1.0, 0.35, -0.6),
+        3.0,
+        Color::new(0.3, 0.3, 0.3),
+        0.04,
+    );
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        lights: vec![sky.sun()],
+        environment: Some(Arc::new(sky)),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 2.0, 9.0),
+        lookat: Point3::new(0.0, 1.5, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn dispersion() {
+    let mut world = HittableList::default();
+
+    let checker = Arc::new(CheckerTexture::from((
+        0.5,
+        &Color::new(0.05, 0.05, 0.05),
+        &Color::new(0.9, 0.9, 0.9),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Material::Lambertian { tex: checker }),
+    )));
+
+    // Crown glass, flint glass and diamond, from weakest to strongest dispersion.
+    for (x, refraction_index, dispersion) in [
+        (-2.2, 1.52, 0.0042),
+        (0.0, 1.62, 0.0106),
+        (2.2, 2.42, 0.0134),
+    ] {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(x, 1.0, 0.0),
+            1.0,
+            Arc::new(Material::Dielectric {
+                refraction_index,
+                dispersion,
+                priority: 0,
+                coating: None,
+            }),
+        )));
+    }
+
+    let sky = PreethamSky::new(
+        Vec3::new(-1.0, 0.6, -0.6),
+        3.0,
+        Color::new(0.3, 0.3, 0.3),
+        0.04,
+    );
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 200,
+        max_depth: 50,
+        lights: vec![sky.sun()],
+        environment: Some(Arc::new(sky)),
+        spectral: true,
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 4.0, 9.0),
+        lookat: Point3::new(0.0, 1.0, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn image_texture() {
+    let mut path = String::new();
+    eprintln!("Path to .ppm/.png image: ");
+    std::io::stdin()
+        .read_line(&mut path)
+        .expect("Invalid input");
+    let texture =
+        Arc::new(ImageTexture::load(path.trim(), WrapMode::Repeat).expect("Could not load image"));
+
+    let mut world = HittableList::default();
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1.2, 0.0, 0.0),
+        1.0,
+        Arc::new(Material::Lambertian {
+            tex: texture.clone(),
+        }),
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.4, -1.0, 0.0),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 2.0, 0.0),
+        Arc::new(Material::Lambertian { tex: texture }),
+        Shape::Quad,
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        background: Color::new(0.7, 0.8, 1.0),
+
+        vfov: 30.0,
+        lookfrom: Point3::new(0.0, 0.0, 8.0),
+        lookat: Point3::new(0.0, 0.0, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn texture_filtering() {
+    let mut world = HittableList::default();
+
+    // Fine checks and marble receding to the horizon alias badly without filtering,
+    // also when seen through the mirror and glass spheres.
+    let checker = Arc::new(CheckerTexture::from((
+        0.1,
+        &Color::new(0.1, 0.1, 0.1),
+        &Color::new(0.9, 0.9, 0.9),
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(-500.0, 0.0, -500.0),
+        Vec3::new(500.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1000.0),
+        Arc::new(Material::Lambertian { tex: checker }),
+        Shape::Quad,
+    )));
+    world.add(Arc::new(Planar::new(
+        Point3::new(0.0, 0.0, -500.0),
+        Vec3::new(500.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 1000.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(NoiseTexture::new(8.0)),
+        }),
+        Shape::Quad,
+    )));
+
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Metal {
+            albedo: Color::new(0.9, 0.9, 0.9),
+            fuzz: 0.0,
+            coating: None,
+        }),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(1.2, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Dielectric {
+            refraction_index: 1.5,
+            dispersion: 0.0,
+            priority: 0,
+            coating: None,
+        }),
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.7, 0.8, 1.0),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 1.5, 6.0),
+        lookat: Point3::new(0.0, 0.8, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn texture_transforms() {
+    let mut world = HittableList::default();
+
+    let uv_checker: Arc<UvCheckerTexture> = Arc::new(UvCheckerTexture::from((
+        8.0,
+        8.0,
+        &Color::new(0.1, 0.1, 0.1),
+        &Color::new(0.9, 0.9, 0.9),
+    )));
+
+    // The same UV checker on a floor quad, tiled and turned by 30 degrees.
+    world.add(Arc::new(Planar::new(
+        Point3::new(-4.0, 0.0, -4.0),
+        Vec3::new(8.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 8.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(TransformTexture::uv(
+                uv_checker.clone(),
+                (2.0, 2.0),
+                30.0,
+                (0.0, 0.0),
+            )),
+        }),
+        Shape::Quad,
+    )));
+
+    // Left: the checker follows the sphere's surface, stretched along v.
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1.3, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(TransformTexture::uv(
+                Arc::new(UvCheckerTexture::from((
+                    8.0,
+                    4.0,
+                    &Color::new(0.8, 0.2, 0.1),
+                    &Color::new(0.9, 0.9, 0.8),
+                ))),
+                (1.0, 1.0),
+                0.0,
+                (0.25, 0.0),
+            )),
+        }),
+    )));
+
+    // Right: a solid checker fixed to the sphere, rotated and shifted in space.
+    let solid = Arc::new(CheckerTexture::from((
+        0.4,
+        &Color::new(0.1, 0.3, 0.7),
+        &Color::new(0.9, 0.9, 0.8),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Point3::new(1.3, 1.0, 0.0),
+        1.0,
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(TransformTexture::point(
+                solid,
+                Vec3::new(1.0, 1.0, 1.0),
+                Vec3::new(45.0, 0.0, 45.0),
+                Vec3::new(0.2, 0.0, 0.0),
+            )),
+        }),
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.7, 0.8, 1.0),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 2.5, 6.0),
+        lookat: Point3::new(0.0, 0.8, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn procedural_patterns() {
+    let mut world = HittableList::default();
+
+    // Flagstones: dark mortar along the cell borders.
+    let mortar = PatternTexture::new(
+        NoisePattern::WorleyEdge,
+        1.5,
+        ColorRamp::from(vec![
+            (0.0, Color::new(0.15, 0.13, 0.12)),
+            (0.08, Color::new(0.15, 0.13, 0.12)),
+            (0.15, Color::new(0.55, 0.52, 0.48)),
+            (1.0, Color::new(0.65, 0.62, 0.58)),
+        ]),
+    )
+    .with_seed(1);
+    world.add(Arc::new(Planar::new(
+        Point3::new(-20.0, 0.0, -20.0),
+        Vec3::new(40.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 40.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(mortar),
+        }),
+        Shape::Quad,
+    )));
+
+    let spheres = [
+        // Wood: warped rings, light earlywood to dark latewood.
+        PatternTexture::new(
+            NoisePattern::Rings,
+            3.0,
+            ColorRamp::from(vec![
+                (0.0, Color::new(0.75, 0.5, 0.25)),
+                (0.7, Color::new(0.6, 0.36, 0.16)),
+                (1.0, Color::new(0.35, 0.18, 0.07)),
+            ]),
+        )
+        .with_warp(0.3, Fractal::new(3, 2.0, 0.5)),
+        // Clouds: soft fBm over a blue sky.
+        PatternTexture::new(
+            NoisePattern::Fbm(Fractal::default()),
+            2.0,
+            ColorRamp::from(vec![
+                (0.4, Color::new(0.2, 0.4, 0.8)),
+                (0.7, Color::new(1.0, 1.0, 1.0)),
+            ]),
+        ),
+        // Ridged multifractal read as a snowy mountain map.
+        PatternTexture::new(
+            NoisePattern::Ridged(Fractal::default()),
+            2.0,
+            ColorRamp::from(vec![
+                (0.3, Color::new(0.1, 0.25, 0.1)),
+                (0.6, Color::new(0.45, 0.4, 0.3)),
+                (0.9, Color::new(0.95, 0.95, 0.95)),
+            ]),
+        ),
+        // Swirled turbulence through a domain warp.
+        PatternTexture::new(
+            NoisePattern::Turbulence(Fractal::new(5, 2.0, 0.5)),
+            2.0,
+            ColorRamp::from(vec![
+                (0.0, Color::new(0.05, 0.05, 0.2)),
+                (0.3, Color::new(0.8, 0.3, 0.1)),
+                (0.6, Color::new(1.0, 0.9, 0.5)),
+            ]),
+        )
+        .with_warp(1.5, Fractal::default()),
+    ];
+    // Seeded, so every run renders the same patterns.
+    for (i, tex) in spheres.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(-3.3 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            Arc::new(Material::Lambertian {
+                tex: Arc::new(tex.with_seed(2 + i as u64)),
+            }),
+        )));
+    }
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.7, 0.8, 1.0),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 3.0, 9.0),
+        lookat: Point3::new(0.0, 0.8, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn layered_textures() {
+    let mut world = HittableList::default();
+
+    world.add(Arc::new(Planar::new(
+        Point3::new(-20.0, 0.0, -20.0),
+        Vec3::new(40.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 40.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::from((0.5, 0.5, 0.5))),
+        }),
+        Shape::Quad,
+    )));
+
+    let noise = |pattern, scale, seed| -> Arc<PatternTexture> {
+        Arc::new(PatternTexture::new(pattern, scale, ColorRamp::default()).with_seed(seed))
+    };
+
+    // Rusted paint: a high-contrast fBm mask mixes blue paint into rust, and the rust
+    // itself is ridged noise through a ramp.
+    let mask = Arc::new(ClampTexture::new(
+        Arc::new(RemapTexture::new(
+            noise(NoisePattern::Fbm(Fractal::default()), 3.0, 1),
+            (0.45, 0.55),
+            (0.0, 1.0),
+        )),
+        0.0,
+        1.0,
+    ));
+    let rust = Arc::new(RampTexture::new(
+        noise(NoisePattern::Ridged(Fractal::default()), 6.0, 2),
+        ColorRamp::from(vec![
+            (0.3, Color::new(0.25, 0.08, 0.03)),
+            (0.9, Color::new(0.7, 0.35, 0.1)),
+        ]),
+    ));
+    let rusted = MixTexture::new(Arc::new(SolidColor::from((0.1, 0.25, 0.6))), rust, mask);
+
+    // Grimy tiles: a UV checker darkened along inverted Worley cell borders.
+    let grime = Arc::new(InvertTexture::new(Arc::new(ClampTexture::new(
+        Arc::new(RemapTexture::new(
+            noise(NoisePattern::WorleyEdge, 4.0, 3),
+            (0.0, 0.2),
+            (0.8, 0.0),
+        )),
+        0.0,
+        1.0,
+    ))));
+    let tiles = MultiplyTexture::new(
+        Arc::new(UvCheckerTexture::from((
+            12.0,
+            6.0,
+            &Color::new(0.9, 0.85, 0.7),
+            &Color::new(0.6, 0.1, 0.1),
+        ))),
+        grime,
+    );
+
+    // Channels: three independent noises packed into red, green and blue, brightened.
+    let channels = AddTexture::new(
+        Arc::new(CombineTexture::new(
+            noise(NoisePattern::Fbm(Fractal::default()), 2.0, 4),
+            noise(NoisePattern::Turbulence(Fractal::default()), 2.0, 5),
+            Arc::new(ChannelTexture::new(
+                noise(NoisePattern::WorleyF1, 3.0, 6),
+                Channel::Red,
+            )),
+        )),
+        Arc::new(SolidColor::from((0.1, 0.1, 0.1))),
+    );
+
+    let spheres: [Arc<dyn Texture>; 3] = [Arc::new(rusted), Arc::new(tiles), Arc::new(channels)];
+    for (i, tex) in spheres.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Point3::new(-2.2 + 2.2 * i as f64, 1.0, 0.0),
+            1.0,
+            Arc::new(Material::Lambertian { tex }),
+        )));
+    }
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.7, 0.8, 1.0),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 2.0, 7.0),
+        lookat: Point3::new(0.0, 0.8, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn bump_mapping() {
+    let mut world = HittableList::default();
+
+    // A tangent-space normal map of round studs, built in memory: each texel encodes the
+    // normal of a hemispherical cap in red, green and blue.
+    let size = 64;
+    let studs = (0..size * size)
+        .map(|i| {
+            let x = 2.0 * ((i % size) as f64 + 0.5) / size as f64 - 1.0;
+            let y = 1.0 - 2.0 * ((i / size) as f64 + 0.5) / size as f64;
+            let r2 = (x * x + y * y) / 0.64;
+            let n = if r2 < 1.0 {
+                Vec3::new(x / 0.8, y / 0.8, (1.0 - r2).sqrt())
+            } else {
+                Vec3::new(0.0, 0.0, 1.0)
+            };
+            0.5 * (n + Vec3::new(1.0, 1.0, 1.0))
+        })
+        .collect();
+    let studs = Arc::new(TransformTexture::uv(
+        Arc::new(ImageTexture::new(
+            Image::new(size, size, studs),
+            WrapMode::Repeat,
+        )),
+        (12.0, 12.0),
+        0.0,
+        (0.0, 0.0),
+    ));
+    let floor = Planar::new(
+        Point3::new(-6.0, 0.0, -6.0),
+        Vec3::new(12.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 12.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::from((0.6, 0.6, 0.6))),
+        }),
+        Shape::Quad,
+    );
+    world.add(Arc::new(Bumped::new(
+        Arc::new(floor),
+        SurfaceDetail::NormalMap {
+            map: studs,
+            strength: 1.0,
+        },
+    )));
+
+    // Hammered metal: Worley cells as shallow dents.
+    let dents = Arc::new(
+        PatternTexture::new(NoisePattern::WorleyF1, 6.0, ColorRamp::default()).with_seed(1),
+    );
+    world.add(Arc::new(Bumped::new(
+        Arc::new(Sphere::new(
+            Point3::new(-2.2, 1.0, 0.0),
+            1.0,
+            Arc::new(Material::Metal {
+                albedo: Color::new(0.8, 0.6, 0.4),
+                fuzz: 0.05,
+                coating: None,
+            }),
+        )),
+        SurfaceDetail::Bump {
+            height: dents,
+            scale: 0.03,
+        },
+    )));
+
+    // Rough plaster: bumps from fBm.
+    let plaster = Arc::new(
+        PatternTexture::new(
+            NoisePattern::Fbm(Fractal::default()),
+            8.0,
+            ColorRamp::default(),
+        )
+        .with_seed(2),
+    );
+    world.add(Arc::new(Bumped::new(
+        Arc::new(Sphere::new(
+            Point3::new(0.0, 1.0, 0.0),
+            1.0,
+            Arc::new(Material::Lambertian {
+                tex: Arc::new(SolidColor::from((0.8, 0.75, 0.7))),
+            }),
+        )),
+        SurfaceDetail::Bump {
+            height: plaster,
+            scale: 0.02,
+        },
+    )));
+
+    // Frosted glass ripples.
+    let ripples = Arc::new(NoiseTexture::with_noise(4.0, Perlin::with_seed(3)));
+    world.add(Arc::new(Bumped::new(
+        Arc::new(Sphere::new(
+            Point3::new(2.2, 1.0, 0.0),
+            1.0,
+            Arc::new(Material::Dielectric {
+                refraction_index: 1.5,
+                dispersion: 0.0,
+                priority: 0,
+                coating: None,
+            }),
+        )),
+        SurfaceDetail::Bump {
+            height: ripples,
+            scale: 0.02,
+        },
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.2, 0.25, 0.35),
+        // A low sun brings out the relief.
+        lights: vec![Light::Directional {
+            direction: Vec3::new(-1.0, -0.6, -0.3),
+            irradiance: Color::new(3.0, 2.8, 2.5),
+            angular_radius: 0.5,
+        }],
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 2.5, 7.0),
+        lookat: Point3::new(0.0, 0.8, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn alpha_cutouts() {
+    let mut world = HittableList::default();
+
+    let ground = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::from((0.45, 0.55, 0.35))),
+    });
+    world.add(Arc::new(Planar::new(
+        Point3::new(-20.0, 0.0, -20.0),
+        Vec3::new(40.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 40.0),
+        ground,
+        Shape::Quad,
+    )));
+
+    // Masks built in memory, white where opaque: a fence lattice tile and a leaf.
+    let mask_image = |size: usize, opaque: &dyn Fn(f64, f64) -> bool| {
+        let data = (0..size * size)
+            .map(|i| {
+                let x = ((i % size) as f64 + 0.5) / size as f64;
+                let y = 1.0 - ((i / size) as f64 + 0.5) / size as f64;
+                let a = if opaque(x, y) { 1.0 } else { 0.0 };
+                Color::new(a, a, a)
+            })
+            .collect();
+        Image::new(size, size, data)
+    };
+    let lattice = mask_image(32, &|x, y| {
+        let (dx, dy) = ((x - 0.5).abs(), (y - 0.5).abs());
+        (dx - dy).abs() < 0.08 || (dx + dy - 0.5).abs() < 0.06
+    });
+    let leaf = mask_image(64, &|x, y| {
+        let (x, y) = (2.0 * x - 1.0, 2.0 * y - 1.0);
+        let width = 0.55 * (1.0 - y * y).max(0.0).sqrt() * (1.0 - 0.3 * y);
+        x.abs() < width || (x.abs() < 0.03 && y < -0.6)
+    });
+
+    let fence = Planar::new(
+        Point3::new(-4.0, 0.0, -2.0),
+        Vec3::new(8.0, 0.0, 0.0),
+        Vec3::new(0.0, 2.0, 0.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::from((0.55, 0.4, 0.25))),
+        }),
+        Shape::Quad,
+    );
+    world.add(Arc::new(AlphaMasked::new(
+        Arc::new(fence),
+        Arc::new(TransformTexture::uv(
+            Arc::new(ImageTexture::new(lattice, WrapMode::Repeat)),
+            (8.0, 2.0),
+            0.0,
+            (0.0, 0.0),
+        )),
+        AlphaMode::Cutout(0.5),
+    )));
+
+    // Leaves scattered in front of the fence, each a quad cut out by the leaf mask.
+    let leaf = Arc::new(ImageTexture::new(leaf, WrapMode::Clamp));
+    let leaf_material = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::from((0.2, 0.5, 0.1))),
+    });
+    for i in 0..12 {
+        let angle = 30.0 * i as f64;
+        let quad = Planar::new(
+            Point3::new(-0.25, 0.0, 0.0),
+            Vec3::new(0.5, 0.0, 0.0),
+            Vec3::new(0.0, 0.8, 0.0),
+            leaf_material.clone(),
+            Shape::Quad,
+        );
+        let masked = AlphaMasked::new(Arc::new(quad), leaf.clone(), AlphaMode::Cutout(0.5));
+        world.add(Arc::new(Translate::new(
+            Arc::new(RotateY::new(Arc::new(masked), angle)),
+            Point3::new(-2.0 + 0.35 * i as f64, 0.6 * (i % 3) as f64, 0.5),
+        )));
+    }
+
+    // Half-transparent screen: alpha 0.5 gives half coverage over the samples.
+    let screen = Planar::new(
+        Point3::new(0.8, 0.0, 1.0),
+        Vec3::new(1.5, 0.0, 0.0),
+        Vec3::new(0.0, 1.5, 0.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::from((0.8, 0.2, 0.2))),
+        }),
+        Shape::Quad,
+    );
+    world.add(Arc::new(AlphaMasked::new(
+        Arc::new(screen),
+        Arc::new(SolidColor::from((0.5, 0.5, 0.5))),
+        AlphaMode::Stochastic,
+    )));
+
+    // A sphere reduced to its cell borders, showing its inside through the holes.
+    let cells = Arc::new(
+        PatternTexture::new(NoisePattern::WorleyEdge, 3.0, ColorRamp::default()).with_seed(1),
+    );
+    world.add(Arc::new(AlphaMasked::new(
+        Arc::new(Sphere::new(
+            Point3::new(-2.2, 0.8, 1.2),
+            0.8,
+            Arc::new(Material::Lambertian {
+                tex: Arc::new(SolidColor::from((0.9, 0.8, 0.3))),
+            }),
+        )),
+        Arc::new(InvertTexture::new(cells)),
+        AlphaMode::Cutout(0.85),
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.2, 0.25, 0.35),
+        lights: vec![Light::Directional {
+            direction: Vec3::new(-0.5, -1.0, -0.7),
+            irradiance: Color::new(3.0, 2.8, 2.5),
+            angular_radius: 0.5,
+        }],
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 2.0, 7.0),
+        lookat: Point3::new(0.0, 0.9, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn projection_mapping() {
+    let mut world = HittableList::default();
+
+    world.add(Arc::new(Planar::new(
+        Point3::new(-20.0, 0.0, -20.0),
+        Vec3::new(40.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 40.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::from((0.5, 0.5, 0.5))),
+        }),
+        Shape::Quad,
+    )));
+
+    let checks = |columns: f64, rows: f64, color: Color| -> Arc<UvCheckerTexture> {
+        Arc::new(UvCheckerTexture::from((
+            columns,
+            rows,
+            &color,
+            &Color::new(0.9, 0.9, 0.85),
+        )))
+    };
+    let projected = |texture: Arc<dyn Texture>, projection, center| {
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(ProjectionTexture::new(texture, projection, center)),
+        })
+    };
+
+    // Left: planar projection straight through a sphere, streaking along z at the sides.
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-3.3, 1.0, 0.0),
+        1.0,
+        projected(
+            checks(4.0, 4.0, Color::new(0.7, 0.2, 0.1)),
+            Projection::Planar,
+            Point3::default(),
+        ),
+    )));
+
+    // Cylindrical projection on a sphere: vertical stripes of constant width in angle.
+    world.add(Arc::new(Sphere::new(
+        Point3::new(-1.1, 1.0, 0.0),
+        1.0,
+        projected(
+            checks(12.0, 4.0, Color::new(0.1, 0.4, 0.7)),
+            Projection::Cylindrical,
+            Point3::new(-1.1, 1.0, 0.0),
+        ),
+    )));
+
+    // A box turned by 30 degrees. The triplanar projection works in the box's own space,
+    // so the checks stay fixed to its faces.
+    let cube = build_box(
+        Point3::new(-0.7, 0.0, -0.7),
+        Point3::new(0.7, 1.4, 0.7),
+        projected(
+            checks(2.0, 2.0, Color::new(0.2, 0.6, 0.2)),
+            Projection::Triplanar { sharpness: 4.0 },
+            Point3::default(),
+        ),
+    );
+    world.add(Arc::new(Translate::new(
+        Arc::new(RotateY::new(cube, 30.0)),
+        Vec3::new(1.1, 0.0, 0.0),
+    )));
+
+    // Spherical projection on a box, in contrast to the sphere's own coordinates.
+    let cube = build_box(
+        Point3::new(2.6, 0.0, -0.7),
+        Point3::new(4.0, 1.4, 0.7),
+        projected(
+            checks(12.0, 6.0, Color::new(0.6, 0.5, 0.1)),
+            Projection::Spherical,
+            Point3::new(3.3, 0.7, 0.0),
+        ),
+    );
+    world.add(cube);
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.7, 0.8, 1.0),
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 3.0, 9.0),
+        lookat: Point3::new(0.0, 0.8, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn displacement() {
+    let mut world = HittableList::default();
+
+    // Ridged noise raises real geometry: its silhouette and shadows show the relief
+    // that bump mapping can only suggest.
+    let height = Arc::new(
+        PatternTexture::new(
+            NoisePattern::Ridged(Fractal::new(5, 2.0, 0.5)),
+            0.4,
+            ColorRamp::default(),
+        )
+        .with_seed(1),
+    );
+    let rock = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::from((0.55, 0.5, 0.45))),
+    });
+    world.add(build_displaced_quad(
+        Point3::new(-5.0, 0.0, 2.0),
+        Vec3::new(10.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -8.0),
+        rock.clone(),
+        height,
+        1.5,
+        12.0,
+    ));
+
+    // A coarse sphere mesh roughened into a boulder. Its smooth vertex normals carry the
+    // displacement, so the facets of the base mesh do not show through.
+    let center = Point3::new(1.8, 1.4, -1.6);
+    let (slices, stacks) = (16, 8);
+    let vertex = |i: usize, j: usize| {
+        let phi = 2.0 * PI * i as f64 / slices as f64;
+        let theta = PI * j as f64 / stacks as f64;
+        let n = Vec3::new(
+            theta.sin() * phi.cos(),
+            theta.cos(),
+            -theta.sin() * phi.sin(),
+        );
+        (
+            center + 0.9 * n,
+            n,
+            (i as f64 / slices as f64, j as f64 / stacks as f64),
+        )
+    };
+    let mut sphere = Vec::new();
+    for j in 0..stacks {
+        for i in 0..slices {
+            for corners in [
+                [(i, j), (i, j + 1), (i + 1, j + 1)],
+                [(i, j), (i + 1, j + 1), (i + 1, j)],
+            ] {
+                let [a, b, c] = corners.map(|(i, j)| vertex(i, j));
+                if (b.0 - a.0).cross(&(c.0 - a.0)).near_zero() {
+                    continue;
+                }
+                sphere.push(Triangle::with_attributes(
+                    [a.0, b.0, c.0],
+                    [a.1, b.1, c.1],
+                    [a.2, b.2, c.2],
+                    rock.clone(),
+                ));
+            }
+        }
+    }
+    world.add(build_displaced_mesh(
+        &sphere,
+        Arc::new(
+            PatternTexture::new(
+                NoisePattern::Turbulence(Fractal::new(4, 2.0, 0.5)),
+                2.0,
+                ColorRamp::default(),
+            )
+            .with_seed(2),
+        ),
+        0.3,
+        24.0,
+    ));
+
+    world.add(Arc::new(Planar::new(
+        Point3::new(-20.0, 0.45, -20.0),
+        Vec3::new(40.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 40.0),
+        Arc::new(Material::Metal {
+            albedo: Color::new(0.3, 0.4, 0.5),
+            fuzz: 0.02,
+            coating: None,
+        }),
+        Shape::Quad,
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.5, 0.6, 0.8),
+        lights: vec![Light::Directional {
+            direction: Vec3::new(-1.0, -0.5, -0.4),
+            irradiance: Color::new(3.0, 2.8, 2.5),
+            angular_radius: 0.5,
+        }],
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 3.0, 5.0),
+        lookat: Point3::new(0.0, 0.6, -1.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn heightfield_terrain() {
+    let mut world = HittableList::default();
+
+    // 256 x 256 samples of ridged noise: a single primitive instead of thousands of
+    // boxes, traversed cell by cell.
+    let samples = 256;
+    let noise = Perlin::with_seed(7);
+    let fractal = Fractal::new(6, 2.0, 0.5);
+    let heights: Vec<f64> = (0..samples * samples)
+        .map(|k| {
+            let (i, j) = ((k % samples) as f64, (k / samples) as f64);
+            let p = Point3::new(i, 0.0, j) * (4.0 / samples as f64);
+            noise.ridged(p, &fractal).powi(4)
+        })
+        .collect();
+
+    // The same heights colored through a ramp, draped over the terrain by its texture
+    // coordinates: the bands follow the contours.
+    let ramp = ColorRamp::from(vec![
+        (0.1, Color::new(0.15, 0.3, 0.1)),
+        (0.3, Color::new(0.35, 0.4, 0.2)),
+        (0.45, Color::new(0.4, 0.33, 0.25)),
+        (0.6, Color::new(0.95, 0.95, 0.95)),
+    ]);
+    let colors = Image::new(
+        samples,
+        samples,
+        heights.iter().map(|&h| ramp.value(h)).collect(),
+    );
+    let terrain = Heightfield::new(
+        Point3::new(-10.0, 0.0, -14.0),
+        Vec3::new(20.0, 3.0, 20.0),
+        samples,
+        samples,
+        &heights,
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(ImageTexture::new(colors, WrapMode::Clamp)),
+        }),
+    );
+    world.add(Arc::new(terrain));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.5, 0.6, 0.8),
+        lights: vec![Light::Directional {
+            direction: Vec3::new(-1.0, -0.6, -0.2),
+            irradiance: Color::new(3.0, 2.8, 2.5),
+            angular_radius: 0.5,
+        }],
+
+        vfov: 45.0,
+        lookfrom: Point3::new(0.0, 6.0, 8.0),
+        lookat: Point3::new(0.0, 1.0, -4.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn quadrics() {
+    let mut world = HittableList::default();
+
+    world.add(Arc::new(Planar::new(
+        Point3::new(-8.0, 0.0, -8.0),
+        Vec3::new(16.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 16.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::from((0.6, 0.6, 0.6))),
+        }),
+        Shape::Quad,
+    )));
+
+    // Checkers in texture space show how each surface is parameterized.
+    let checker = |color: Color| {
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(UvCheckerTexture::from((
+                16.0,
+                8.0,
+                &color,
+                &Color::new(0.9, 0.9, 0.9),
+            ))),
+        })
+    };
+
+    world.add(Arc::new(Cylinder::new(
+        Point3::new(-3.0, 0.0, 0.0),
+        Vec3::new(0.0, 2.0, 0.0),
+        0.8,
+        true,
+        checker(Color::new(0.7, 0.2, 0.1)),
+    )));
+    world.add(Arc::new(Cone::new(
+        Point3::new(-1.0, 0.0, -0.5),
+        Vec3::new(0.0, 2.2, 0.0),
+        0.8,
+        true,
+        checker(Color::new(0.1, 0.3, 0.7)),
+    )));
+    world.add(Arc::new(Torus::new(
+        Point3::new(1.2, 0.9, 0.0),
+        Vec3::new(0.0, 1.0, -1.2),
+        0.7,
+        0.25,
+        checker(Color::new(0.2, 0.6, 0.2)),
+    )));
+
+    // An open metal tube resting on its side, and a washer lying flat.
+    let steel = Arc::new(Material::Metal {
+        albedo: Color::new(0.8, 0.8, 0.85),
+        fuzz: 0.1,
+        coating: None,
+    });
+    world.add(Arc::new(Cylinder::new(
+        Point3::new(2.6, 0.5, -1.5),
+        Vec3::new(0.0, 0.0, 2.0),
+        0.5,
+        false,
+        steel.clone(),
+    )));
+    world.add(Arc::new(Disk::new(
+        Point3::new(-0.5, 0.01, 1.6),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.6,
+        0.3,
+        steel,
+    )));
+    world.add(Arc::new(Torus::new(
+        Point3::new(1.0, 0.2, 1.8),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.4,
+        0.2,
+        Arc::new(Material::Dielectric {
+            refraction_index: 1.5,
+            dispersion: 0.0,
+            priority: 0,
+            coating: None,
+        }),
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 64,
+        max_depth: 50,
+        background: Color::new(0.6, 0.7, 0.9),
+        lights: vec![Light::Directional {
+            direction: Vec3::new(-0.6, -1.0, -0.4),
+            irradiance: Color::new(2.5, 2.4, 2.2),
+            angular_radius: 1.0,
+        }],
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 4.0, 8.0),
+        lookat: Point3::new(0.0, 0.8, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn constructive_solids() {
+    let mut world = HittableList::default();
+
+    world.add(Arc::new(Planar::new(
+        Point3::new(-8.0, 0.0, -8.0),
+        Vec3::new(16.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 16.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(CheckerTexture::from((
+                0.5,
+                &Color::new(0.3, 0.3, 0.3),
+                &Color::new(0.8, 0.8, 0.8),
+            ))),
+        }),
+        Shape::Quad,
+    )));
+
+    // A biconvex lens: where two large glass spheres overlap.
+    let glass = Arc::new(Material::Dielectric {
+        refraction_index: 1.5,
+        dispersion: 0.0,
+        priority: 0,
+        coating: None,
+    });
+    let lens = Csg::intersection(
+        Arc::new(Sphere::new(
+            Point3::new(-1.28, 1.2, -0.96),
+            2.0,
+            glass.clone(),
+        )),
+        Arc::new(Sphere::new(Point3::new(1.28, 1.2, 0.96), 2.0, glass)),
+    );
+    world.add(Arc::new(lens));
+
+    // A block drilled through along two axes; the bore walls keep the drills' material.
+    let block = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::from((0.8, 0.3, 0.1))),
+    });
+    let bore = Arc::new(Material::Metal {
+        albedo: Color::new(0.8, 0.8, 0.8),
+        fuzz: 0.2,
+        coating: None,
+    });
+    let drilled = Csg::difference(
+        Arc::new(Csg::difference(
+            build_box(
+                Point3::new(-3.8, 0.0, -0.8),
+                Point3::new(-2.2, 1.6, 0.8),
+                block,
+            ),
+            Arc::new(Cylinder::new(
+                Point3::new(-3.0, 0.8, -1.0),
+                Vec3::new(0.0, 0.0, 2.0),
+                0.5,
+                true,
+                bore.clone(),
+            )),
+        )),
+        Arc::new(Cylinder::new(
+            Point3::new(-4.0, 0.8, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            0.35,
+            true,
+            bore,
+        )),
+    );
+    world.add(Arc::new(drilled));
+
+    // A bowl: a sphere hollowed out by a smaller one and cut open by a box, with a ball
+    // welded on as its handle by a union.
+    let ceramic = Arc::new(Material::Lambertian {
+        tex: Arc::new(SolidColor::from((0.2, 0.4, 0.8))),
+    });
+    let shell = Csg::difference(
+        Arc::new(Sphere::new(
+            Point3::new(3.0, 1.0, 0.0),
+            1.0,
+            ceramic.clone(),
+        )),
+        Arc::new(Sphere::new(
+            Point3::new(3.0, 1.0, 0.0),
+            0.9,
+            ceramic.clone(),
+        )),
+    );
+    let bowl = Csg::difference(
+        Arc::new(shell),
+        build_box(
+            Point3::new(1.8, 1.3, -1.2),
+            Point3::new(4.2, 2.2, 1.2),
+            ceramic.clone(),
+        ),
+    );
+    world.add(Arc::new(Csg::union(
+        Arc::new(bowl),
+        Arc::new(Sphere::new(Point3::new(4.0, 1.0, 0.0), 0.25, ceramic)),
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 64,
+        max_depth: 50,
+        background: Color::new(0.6, 0.7, 0.9),
+        lights: vec![Light::Directional {
+            direction: Vec3::new(-0.5, -1.0, -0.6),
+            irradiance: Color::new(2.5, 2.4, 2.2),
+            angular_radius: 1.0,
+        }],
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 4.5, 8.0),
+        lookat: Point3::new(0.0, 0.8, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
+fn distance_fields() {
+    let mut world = HittableList::default();
+
+    world.add(Arc::new(Planar::new(
+        Point3::new(-8.0, 0.0, -8.0),
+        Vec3::new(16.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, 16.0),
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::from((0.6, 0.6, 0.6))),
+        }),
+        Shape::Quad,
+    )));
+
+    let solid = |r: f64, g: f64, b: f64| {
+        Arc::new(Material::Lambertian {
+            tex: Arc::new(SolidColor::from((r, g, b))),
+        })
+    };
+
+    // A Mandelbulb, whose only description is its distance estimate.
+    let mandelbulb = Sdf::Translate(
+        Arc::new(Sdf::Mandelbulb {
+            power: 8.0,
+            iterations: 8,
+        }),
+        Vec3::new(-3.0, 1.2, 0.0),
+    );
+    world.add(Arc::new(
+        SdfObject::new(
+            mandelbulb,
+            AABB::from((Point3::new(-4.3, -0.1, -1.3), Point3::new(-1.7, 2.5, 1.3))),
+            solid(0.8, 0.5, 0.3),
+        )
+        .with_tolerance(1e-3),
+    ));
+
+    // Blobs: spheres melted together with smooth unions.
+    let blob = |center: Point3, radius: f64| {
+        Arc::new(Sdf::Translate(Arc::new(Sdf::Sphere { radius }), center))
+    };
+    let blobs = Sdf::SmoothUnion(
+        Arc::new(Sdf::SmoothUnion(
+            blob(Point3::new(-0.4, 0.6, 0.0), 0.6),
+            blob(Point3::new(0.4, 0.8, 0.2), 0.5),
+            0.4,
+        )),
+        blob(Point3::new(0.0, 1.5, -0.1), 0.4),
+        0.4,
+    );
+    world.add(Arc::new(SdfObject::new(
+        blobs,
+        AABB::from((Point3::new(-1.1, -0.1, -0.8), Point3::new(1.0, 2.0, 0.8))),
+        Arc::new(Material::Dielectric {
+            refraction_index: 1.4,
+            dispersion: 0.0,
+            priority: 0,
+            coating: None,
+        }),
+    )));
+
+    // A twisted rounded column with a smooth hollow carved out of its middle.
+    let column = Sdf::SmoothSubtraction(
+        Arc::new(Sdf::Twist(
+            Arc::new(Sdf::RoundBox {
+                half_extents: Vec3::new(0.45, 1.1, 0.45),
+                radius: 0.1,
+            }),
+            60.0,
+        )),
+        Arc::new(Sdf::Torus {
+            major_radius: 0.6,
+            minor_radius: 0.25,
+        }),
+        0.1,
+    );
+    world.add(Arc::new(
+        SdfObject::new(
+            Sdf::Translate(Arc::new(column), Vec3::new(2.6, 1.1, 0.0)),
+            AABB::from((Point3::new(1.8, 0.0, -0.8), Point3::new(3.4, 2.2, 0.8))),
+            solid(0.2, 0.4, 0.8),
+        )
+        .with_step_scale(0.6),
+    ));
+
+    // A row of capsules from a single repeated one, cut off by the bounds.
+    let capsules = Sdf::Translate(
+        Arc::new(Sdf::Repeat(
+            Arc::new(Sdf::Capsule {
+                a: Point3::new(0.0, 0.15, -0.3),
+                b: Point3::new(0.0, 0.15, 0.3),
+                radius: 0.15,
+            }),
+            Vec3::new(0.6, 0.0, 0.0),
+        )),
+        Vec3::new(0.0, 0.0, 2.0),
+    );
+    world.add(Arc::new(SdfObject::new(
+        capsules,
+        AABB::from((Point3::new(-2.7, 0.0, 1.6), Point3::new(2.7, 0.3, 2.4))),
+        Arc::new(Material::Metal {
+            albedo: Color::new(0.8, 0.8, 0.8),
+            fuzz: 0.1,
+            coating: None,
+        }),
+    )));
+
+    let mut cam = Camera {
+        aspect_ratio: 16.0 / 9.0,
+        image_width: 400,
+        samples_per_pixel: 16,
+        max_depth: 50,
+        background: Color::new(0.6, 0.7, 0.9),
+        lights: vec![Light::Directional {
+            direction: Vec3::new(-0.5, -1.0, -0.6),
+            irradiance: Color::new(2.5, 2.4, 2.2),
+            angular_radius: 1.0,
+        }],
+
+        vfov: 40.0,
+        lookfrom: Point3::new(0.0, 4.0, 8.5),
+        lookat: Point3::new(0.0, 0.9, 0.0),
+        vup: Vec3::new(0.0, 1.0, 0.0),
+
+        defocus_angle: 0.0,
+        ..Camera::default()
+    };
+
+    cam.render(Arc::new(world));
+}
+
 fn main() {
     let mut scene = String::new();
     eprintln!("Input scene index: ");
@@ -689,6 +2791,28 @@ fn main() {
     eprintln!("-- 6. Cornell Smoke");
     eprintln!("-- 7. Final Scene Test");
     eprintln!("-- 8. Final Scene Release");
+    eprintln!("-- 9. Nested Dielectrics");
+    eprintln!("-- 10. Scene Lights");
+    eprintln!("-- 11. Environment Map");
+    eprintln!("-- 12. Physical Sky");
+    eprintln!("-- 13. Cornell Plume");
+    eprintln!("-- 14. Cornell Media");
+    eprintln!("-- 15. Cornell Subsurface");
+    eprintln!("-- 16. Thin Films");
+    eprintln!("-- 17. Dispersion");
+    eprintln!("-- 18. Image Texture");
+    eprintln!("-- 19. Texture Filtering");
+    eprintln!("-- 20. Texture Transforms");
+    eprintln!("-- 21. Procedural Patterns");
+    eprintln!("-- 22. Layered Textures");
+    eprintln!("-- 23. Bump Mapping");
+    eprintln!("-- 24. Alpha Cutouts");
+    eprintln!("-- 25. Projection Mapping");
+    eprintln!("-- 26. Displacement");
+    eprintln!("-- 27. Heightfield Terrain");
+    eprintln!("-- 28. Quadrics");
+    eprintln!("-- 29. Constructive Solids");
+    eprintln!("-- 30. Distance Fields");
     std::io::stdin()
         .read_line(&mut scene)
         .expect("Invalid input");
@@ -703,6 +2827,28 @@ fn main() {
         Ok(6) => cornell_smoke(),
         Ok(7) => final_scene(400, 250, 4),
         Ok(8) => final_scene(800, 10000, 40),
+        Ok(9) => nested_dielectrics(),
+        Ok(10) => scene_lights(),
+        Ok(11) => environment_map(),
+        Ok(12) => physical_sky(),
+        Ok(13) => cornell_plume(),
+        Ok(14) => cornell_media(),
+        Ok(15) => cornell_subsurface(),
+        Ok(16) => thin_films(),
+        Ok(17) => dispersion(),
+        Ok(18) => image_texture(),
+        Ok(19) => texture_filtering(),
+        Ok(20) => texture_transforms(),
+        Ok(21) => procedural_patterns(),
+        Ok(22) => layered_textures(),
+        Ok(23) => bump_mapping(),
+        Ok(24) => alpha_cutouts(),
+        Ok(25) => projection_mapping(),
+        Ok(26) => displacement(),
+        Ok(27) => heightfield_terrain(),
+        Ok(28) => quadrics(),
+        Ok(29) => constructive_solids(),
+        Ok(30) => distance_fields(),
         _ => {
             eprintln!("Invalid Scene index: {scene}");
         }
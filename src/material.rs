@@ -1,25 +1,77 @@
-use std::sync::Arc;
+use std::{f64::consts::PI, sync::Arc};
 
 use crate::{
-    color::Color,
+    color::{rgb_to_spectrum, spectrum_to_rgb, Color},
+    density::DensityField,
+    onb::ONB,
     primitive::HitRecord,
-    ray::Ray,
+    ray::{Ray, RayDifferentials},
     texture::Texture,
     utils::random_double,
-    vec3::{Point3, Vec3},
+    vec3::Vec3,
 };
 
 #[derive(Clone, Debug)]
 pub enum Material {
-    Lambertian { tex: Arc<dyn Texture> },
-    Metal { albedo: Color, fuzz: f64 },
-    Dielectric { refraction_index: f64 },
-    DiffuseLight { tex: Arc<dyn Texture> },
-    Isotropic { tex: Arc<dyn Texture> },
+    Lambertian {
+        tex: Arc<dyn Texture>,
+    },
+    Metal {
+        albedo: Color,
+        fuzz: f64,
+        coating: Option<ThinFilm>,
+    },
+    /// `refraction_index` is at 589.3 nm. When rendering spectrally, `dispersion` is the
+    /// Cauchy coefficient B in µm² that bends shorter wavelengths more; 0 disables it.
+    Dielectric {
+        refraction_index: f64,
+        dispersion: f64,
+        priority: u32,
+        coating: Option<ThinFilm>,
+    },
+    DiffuseLight {
+        tex: Arc<dyn Texture>,
+        scale: f64,
+        two_sided: bool,
+        profile: EmissionProfile,
+    },
+    Isotropic {
+        tex: Arc<dyn Texture>,
+    },
+    Volume {
+        tex: Arc<dyn Texture>,
+        phase: PhaseFunction,
+    },
+    /// Tentative collision inside a medium sampled against `majorant`; `scatter` decides
+    /// whether it absorbs, scatters or is a null collision. Coefficients are scaled by
+    /// `density` at the hit point when there is one.
+    Medium {
+        coefficients: MediumCoefficients,
+        density: Option<Arc<dyn DensityField>>,
+        majorant: f64,
+    },
+    /// Translucent solid such as wax, skin or marble, whose closed boundary refracts like
+    /// a dielectric and whose interior is walked by the camera with `interior_step`.
+    /// `albedo` is the color the material appears to have overall and `mean_free_path`
+    /// how far light travels between interactions, per channel, in scene units.
+    Subsurface {
+        albedo: Color,
+        mean_free_path: Color,
+        refraction_index: f64,
+        phase: PhaseFunction,
+    },
 }
 
 impl Material {
-    pub fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+    /// The ray leaving `rec` and its attenuation, or `None` if the path ends here. The
+    /// flag is set when the ray carries straight on unchanged, as at a null collision or
+    /// a false interface between dielectrics, rather than bouncing.
+    pub fn scatter(
+        &self,
+        r_in: Ray,
+        rec: &HitRecord,
+        media: &mut MediumStack,
+    ) -> Option<(Ray, Color, bool)> {
         match self {
             Self::Lambertian { tex } => {
                 let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
@@ -30,59 +82,334 @@ impl Material {
 
                 Some((
                     Ray::new(rec.p, scatter_direction, r_in.time()),
-                    tex.value(rec.u, rec.v, &rec.p),
+                    tex.filtered_value(rec.u, rec.v, &rec.p, &rec.footprint),
+                    false,
                 ))
             }
-            Self::Metal { albedo, fuzz } => {
+            Self::Metal {
+                albedo,
+                fuzz,
+                coating,
+            } => {
                 let fuzz = fuzz.min(1.0);
                 let reflected = r_in.direction().reflect(&rec.normal);
                 let reflected = reflected.unit_vector() + fuzz * Vec3::random_unit_vector();
-                let scattered = Ray::new(rec.p, reflected, r_in.time());
-                if scattered.direction().dot(&rec.normal) > 0.0 {
-                    Some((scattered, *albedo))
-                } else {
-                    None
+                let scattered = Ray::new(rec.p, reflected, r_in.time()).with_differentials(
+                    reflected_differentials(&r_in, rec, &reflected.unit_vector()),
+                );
+                if scattered.direction().dot(&rec.normal) <= 0.0 {
+                    return None;
                 }
+                let attenuation = match coating {
+                    Some(film) => {
+                        let cos_theta = (-r_in.direction().unit_vector()).dot(&rec.normal);
+                        let substrate = Substrate::Conductor(*albedo);
+                        let wavelength = r_in.wavelength();
+                        film.reflectance_rgb(
+                            rec,
+                            cos_theta,
+                            media.refraction_index(None, wavelength),
+                            substrate,
+                            wavelength,
+                        )
+                    }
+                    None => *albedo,
+                };
+                Some((scattered, attenuation, false))
             }
-            Self::Dielectric { refraction_index } => {
-                let attenuation = Color::new(1.0, 1.0, 1.0);
+            Self::Dielectric { .. } | Self::Subsurface { .. } => {
+                let wavelength = r_in.wavelength();
+                let (refraction_index, priority) = self.interface(wavelength)?;
+                let passthrough = Color::new(1.0, 1.0, 1.0);
+
+                // A lower priority dielectric overlapped by a higher one is a false interface:
+                // the ray passes straight through and only the medium stack changes.
                 let ri = if rec.front_face {
-                    1.0 / refraction_index
+                    if media.outranks(priority, None) {
+                        media.push(rec.mat.clone());
+                        return Some((passed_through(&r_in, rec), passthrough, true));
+                    }
+                    media.refraction_index(None, wavelength) / refraction_index
+                } else if media.contains(&rec.mat) {
+                    if media.outranks(priority, Some(&rec.mat)) {
+                        media.remove(&rec.mat);
+                        return Some((passed_through(&r_in, rec), passthrough, true));
+                    }
+                    refraction_index / media.refraction_index(Some(&rec.mat), wavelength)
                 } else {
-                    *refraction_index
+                    refraction_index / media.refraction_index(None, wavelength)
                 };
 
                 let unit_d = r_in.direction().unit_vector();
                 let cos_theta = (-unit_d).dot(&rec.normal).min(1.0);
                 let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
-                let reflectance = {
-                    let r0 = (1.0 - ri) / (1.0 + ri);
-                    let r0 = r0 * r0;
-                    r0 + (1.0 - r0) * (1.0 - cos_theta).powf(5.0)
+                let reflect = |attenuation: Color| {
+                    let direction = unit_d.reflect(&rec.normal);
+                    let differentials = reflected_differentials(&r_in, rec, &direction);
+                    let scattered = Ray::new(rec.p, direction, r_in.time());
+                    Some((
+                        scattered.with_differentials(differentials),
+                        attenuation,
+                        false,
+                    ))
                 };
+                if ri * sin_theta > 1.0 {
+                    return reflect(passthrough);
+                }
 
-                let direction = if ri * sin_theta > 1.0 || reflectance > random_double() {
-                    unit_d.reflect(&rec.normal)
-                } else {
-                    unit_d.refract(&rec.normal, ri)
+                // A coating makes the reflectance colored: choose by its average and
+                // reweight each channel, which leaves uncoated dielectrics unweighted.
+                let reflectance = match self.coating() {
+                    Some(film) => {
+                        let incident = if rec.front_face {
+                            media.refraction_index(None, wavelength)
+                        } else {
+                            refraction_index
+                        };
+                        let substrate = Substrate::Dielectric(1.0 / ri);
+                        film.reflectance_rgb(rec, cos_theta, incident, substrate, wavelength)
+                    }
+                    None => {
+                        let r = schlick(cos_theta, ri);
+                        Color::new(r, r, r)
+                    }
                 };
+                let p_reflect = (reflectance.x + reflectance.y + reflectance.z) / 3.0;
 
-                Some((Ray::new(rec.p, direction, r_in.time()), attenuation))
+                if random_double() < p_reflect {
+                    reflect(reflectance / p_reflect)
+                } else {
+                    if rec.front_face {
+                        media.push(rec.mat.clone());
+                    } else {
+                        media.remove(&rec.mat);
+                    }
+                    let direction = unit_d.refract(&rec.normal, ri);
+                    let differentials = refracted_differentials(&r_in, rec, &direction, ri);
+                    let scattered = Ray::new(rec.p, direction, r_in.time());
+                    let attenuation = (passthrough - reflectance) / (1.0 - p_reflect);
+                    Some((
+                        scattered.with_differentials(differentials),
+                        attenuation,
+                        false,
+                    ))
+                }
             }
             Self::Isotropic { tex } => {
                 let scattered = Ray::new(rec.p, Vec3::random_unit_vector(), r_in.time());
-                let attenuation = tex.value(rec.u, rec.v, &rec.p);
+                let attenuation = tex.filtered_value(rec.u, rec.v, &rec.p, &rec.footprint);
+
+                Some((scattered, attenuation, false))
+            }
+            Self::Volume { tex, phase } => {
+                let direction = phase.sample(&r_in.direction());
+                let scattered = Ray::new(rec.p, direction, r_in.time());
+                let attenuation = tex.filtered_value(rec.u, rec.v, &rec.p, &rec.footprint);
 
-                Some((scattered, attenuation))
+                Some((scattered, attenuation, false))
+            }
+            Self::Medium {
+                coefficients,
+                density,
+                majorant,
+            } => {
+                // Spectral tracking: pick the event by channel-averaged probabilities and
+                // weight each channel by how much more or less likely it was for it.
+                let d = density.as_ref().map_or(1.0, |f| f.density(&rec.p));
+                let sigma_a = d * coefficients.sigma_a;
+                let sigma_s = d * coefficients.sigma_s;
+                let sigma_t = sigma_a + sigma_s;
+                let sigma_n = Color::new(
+                    (majorant - sigma_t.x).max(0.0),
+                    (majorant - sigma_t.y).max(0.0),
+                    (majorant - sigma_t.z).max(0.0),
+                );
+                let avg = |c: &Color| (c.x + c.y + c.z) / 3.0;
+                let (p_a, p_s, p_n) = (avg(&sigma_a), avg(&sigma_s), avg(&sigma_n));
+
+                let total = p_a + p_s + p_n;
+                let xi = random_double() * total;
+                if xi < p_a {
+                    None
+                } else if xi < p_a + p_s {
+                    let direction = coefficients.phase.sample(&r_in.direction());
+                    let attenuation = sigma_s * (total / (majorant * p_s));
+                    Some((Ray::new(rec.p, direction, r_in.time()), attenuation, false))
+                } else {
+                    let attenuation = sigma_n * (total / (majorant * p_n));
+                    Some((
+                        Ray::new(rec.p, r_in.direction(), r_in.time()),
+                        attenuation,
+                        true,
+                    ))
+                }
             }
             _ => None,
         }
     }
 
-    pub fn emitted(&self, u: f64, v: f64, p: Point3) -> Color {
+    /// Fraction of light arriving from unit direction `wi` that is scattered back along
+    /// `r_in`, cosine term included. Zero for materials that only scatter specularly.
+    pub fn eval(&self, r_in: &Ray, rec: &HitRecord, wi: &Vec3) -> Color {
         match self {
-            Self::DiffuseLight { tex } => tex.value(u, v, &p),
+            Self::Lambertian { tex } => {
+                let cosine = rec.normal.dot(wi).max(0.0);
+                tex.filtered_value(rec.u, rec.v, &rec.p, &rec.footprint) * (cosine / PI)
+            }
+            Self::Isotropic { tex } => {
+                tex.filtered_value(rec.u, rec.v, &rec.p, &rec.footprint) / (4.0 * PI)
+            }
+            Self::Volume { tex, phase } => {
+                tex.filtered_value(rec.u, rec.v, &rec.p, &rec.footprint)
+                    * phase.eval(&r_in.direction(), wi)
+            }
+            Self::Medium {
+                coefficients,
+                density,
+                majorant,
+            } => {
+                let d = density.as_ref().map_or(1.0, |f| f.density(&rec.p));
+                (d / majorant)
+                    * coefficients.sigma_s
+                    * coefficients.phase.eval(&r_in.direction(), wi)
+            }
+            _ => Color::default(),
+        }
+    }
+
+    /// Solid angle pdf with which `scatter` picks the unit direction `wi`.
+    pub fn pdf(&self, r_in: &Ray, rec: &HitRecord, wi: &Vec3) -> f64 {
+        match self {
+            Self::Lambertian { .. } => rec.normal.dot(wi).max(0.0) / PI,
+            Self::Isotropic { .. } => 1.0 / (4.0 * PI),
+            Self::Volume { phase, .. } => phase.eval(&r_in.direction(), wi),
+            Self::Medium { coefficients, .. } => coefficients.phase.eval(&r_in.direction(), wi),
+            _ => 0.0,
+        }
+    }
+
+    pub fn is_specular(&self) -> bool {
+        matches!(
+            self,
+            Self::Metal { .. } | Self::Dielectric { .. } | Self::Subsurface { .. }
+        )
+    }
+
+    /// Refraction index at `wavelength` and nesting priority of materials with a
+    /// refractive boundary.
+    fn interface(&self, wavelength: Option<f64>) -> Option<(f64, u32)> {
+        match self {
+            Self::Dielectric {
+                refraction_index,
+                dispersion,
+                priority,
+                ..
+            } => {
+                let refraction_index = match wavelength {
+                    Some(wavelength) => {
+                        // Cauchy's equation, anchored at the sodium D line.
+                        let microns = wavelength / 1000.0;
+                        refraction_index
+                            + dispersion * (1.0 / (microns * microns) - 1.0 / 0.5893f64.powi(2))
+                    }
+                    None => *refraction_index,
+                };
+                Some((refraction_index, *priority))
+            }
+            Self::Subsurface {
+                refraction_index, ..
+            } => Some((*refraction_index, 0)),
+            _ => None,
+        }
+    }
+
+    fn coating(&self) -> Option<&ThinFilm> {
+        match self {
+            Self::Metal { coating, .. } | Self::Dielectric { coating, .. } => coating.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// One step of a random walk inside a subsurface material, along `r` towards the
+    /// boundary it hits at `t_max`. Returns the scattered ray if the walk scatters before
+    /// reaching the boundary, `None` if it gets there, along with the path weight.
+    pub fn interior_step(&self, r: &Ray, t_max: f64) -> (Option<Ray>, Color) {
+        let Self::Subsurface {
+            albedo,
+            mean_free_path,
+            phase,
+            ..
+        } = self
+        else {
+            return (None, Color::new(1.0, 1.0, 1.0));
+        };
+
+        let mut sigma_t = Color::default();
+        let mut sigma_s = Color::default();
+        for c in 0..3 {
+            sigma_t[c] = 1.0 / mean_free_path[c].max(1e-9);
+            sigma_s[c] = single_scattering_albedo(albedo[c]) * sigma_t[c];
+        }
+        let transmittance = |distance: f64| {
+            Color::new(
+                (-sigma_t.x * distance).exp(),
+                (-sigma_t.y * distance).exp(),
+                (-sigma_t.z * distance).exp(),
+            )
+        };
+        let avg = |c: Color| (c.x + c.y + c.z) / 3.0;
+
+        // Sample the distance with a random channel's extinction and weight by the
+        // pdf averaged over all three, so every channel stays unbiased.
+        let ray_length = r.direction().length();
+        let channel = ((random_double() * 3.0) as i32).min(2);
+        let distance = -(1.0 - random_double()).ln() / sigma_t[channel];
+        let boundary = t_max * ray_length;
+
+        if distance < boundary {
+            let tr = transmittance(distance);
+            let pdf = avg(sigma_t * tr);
+            let p = r.at(distance / ray_length);
+            let scattered = Ray::new(p, phase.sample(&r.direction()), r.time());
+            (Some(scattered), sigma_s * tr / pdf)
+        } else {
+            let tr = transmittance(boundary);
+            let probability = avg(tr);
+            if probability <= 0.0 {
+                return (None, Color::default());
+            }
+            (None, tr / probability)
+        }
+    }
+
+    /// Radiance leaving the surface back along `r_in`. One-sided emitters only light the
+    /// side their geometric normal points to.
+    pub fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        match self {
+            Self::DiffuseLight {
+                tex,
+                scale,
+                two_sided,
+                profile,
+            } => {
+                if !rec.front_face && !two_sided {
+                    return Color::default();
+                }
+                let cos_theta = r_in.direction().unit_vector().dot(&rec.normal).abs();
+                *scale
+                    * profile.falloff(cos_theta)
+                    * tex.filtered_value(rec.u, rec.v, &rec.p, &rec.footprint)
+            }
+            // Collision estimator: every tentative collision gathers emission / majorant.
+            Self::Medium {
+                coefficients,
+                density,
+                majorant,
+            } => {
+                let d = density.as_ref().map_or(1.0, |f| f.density(&rec.p));
+                (d / majorant) * coefficients.emission
+            }
             _ => Color::default(),
         }
     }
@@ -90,3 +417,387 @@ impl Material {
 
 unsafe impl Send for Material {}
 unsafe impl Sync for Material {}
+
+/// `r_in` continuing unchanged past `rec`, as through a false interface.
+fn passed_through(r_in: &Ray, rec: &HitRecord) -> Ray {
+    let differentials = r_in.differentials().map(|diff| RayDifferentials {
+        rx_origin: rec.p + rec.footprint.dpdx,
+        ry_origin: rec.p + rec.footprint.dpdy,
+        ..diff
+    });
+    Ray::new(rec.p, r_in.direction(), r_in.time()).with_differentials(differentials)
+}
+
+/// How the unit normal and the direction towards the viewer change per pixel at `rec`.
+fn differential_terms(diff: &RayDifferentials, r_in: &Ray, rec: &HitRecord) -> [(Vec3, Vec3); 2] {
+    let fp = &rec.footprint;
+    let wo = -r_in.direction().unit_vector();
+    let dndx = fp.dudx * rec.dndu + fp.dvdx * rec.dndv;
+    let dndy = fp.dudy * rec.dndu + fp.dvdy * rec.dndv;
+    let dwodx = -diff.rx_direction.unit_vector() - wo;
+    let dwody = -diff.ry_direction.unit_vector() - wo;
+    [(dndx, dwodx), (dndy, dwody)]
+}
+
+/// Differentials of the mirror reflection of `r_in` about the normal into unit `wi`,
+/// following pbrt's specular reflection.
+fn reflected_differentials(r_in: &Ray, rec: &HitRecord, wi: &Vec3) -> Option<RayDifferentials> {
+    let diff = r_in.differentials()?;
+    let n = rec.normal;
+    let wo = -r_in.direction().unit_vector();
+    let [x, y] = differential_terms(&diff, r_in, rec).map(|(dndx, dwodx)| {
+        let dcos = dwodx.dot(&n) + wo.dot(&dndx);
+        *wi - dwodx + 2.0 * (wo.dot(&n) * dndx + dcos * n)
+    });
+    Some(RayDifferentials {
+        rx_origin: rec.p + rec.footprint.dpdx,
+        rx_direction: x,
+        ry_origin: rec.p + rec.footprint.dpdy,
+        ry_direction: y,
+    })
+}
+
+/// Differentials of `r_in` refracted into unit `wi` with relative index `ri`, from
+/// differentiating wi = -ri wo + (ri cos_i - cos_t) n.
+fn refracted_differentials(
+    r_in: &Ray,
+    rec: &HitRecord,
+    wi: &Vec3,
+    ri: f64,
+) -> Option<RayDifferentials> {
+    let diff = r_in.differentials()?;
+    let n = rec.normal;
+    let wo = -r_in.direction().unit_vector();
+    let cos_i = wo.dot(&n);
+    let cos_t = wi.dot(&n).abs().max(1e-8);
+    let mu = ri * cos_i - cos_t;
+    let [x, y] = differential_terms(&diff, r_in, rec).map(|(dndx, dwodx)| {
+        let dcos = dwodx.dot(&n) + wo.dot(&dndx);
+        let dmu = (ri - ri * ri * cos_i / cos_t) * dcos;
+        *wi - ri * dwodx + mu * dndx + dmu * n
+    });
+    Some(RayDifferentials {
+        rx_origin: rec.p + rec.footprint.dpdx,
+        rx_direction: x,
+        ry_origin: rec.p + rec.footprint.dpdy,
+        ry_direction: y,
+    })
+}
+
+/// Schlick's approximation of the Fresnel reflectance for relative index `ri`.
+fn schlick(cos_theta: f64, ri: f64) -> f64 {
+    let r0 = (1.0 - ri) / (1.0 + ri);
+    let r0 = r0 * r0;
+    r0 + (1.0 - r0) * (1.0 - cos_theta).powf(5.0)
+}
+
+/// What lies under a thin film, relative to the medium above it.
+#[derive(Clone, Copy, Debug)]
+enum Substrate {
+    /// Dielectric of this relative refraction index.
+    Dielectric(f64),
+    /// Conductor with this normal incidence reflectance.
+    Conductor(Color),
+}
+
+/// Transparent film a few hundred nanometres thick over a surface, such as a soap
+/// bubble wall or a lens coating. Light reflected off its two faces interferes, tinting
+/// the reflection with angle and thickness. `thickness` is in nanometres and is scaled
+/// by the first channel of `thickness_map` when one is given.
+#[derive(Clone, Debug)]
+pub struct ThinFilm {
+    pub thickness: f64,
+    pub refraction_index: f64,
+    pub thickness_map: Option<Arc<dyn Texture>>,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: f64, refraction_index: f64) -> Self {
+        Self {
+            thickness,
+            refraction_index,
+            thickness_map: None,
+        }
+    }
+
+    fn thickness_at(&self, rec: &HitRecord) -> f64 {
+        match &self.thickness_map {
+            Some(map) => self.thickness * map.value(rec.u, rec.v, &rec.p).x.max(0.0),
+            None => self.thickness,
+        }
+    }
+
+    /// Reflectance of the film over the visible spectrum as linear RGB, for light
+    /// arriving at `cos_theta` from a medium of index `outer`. Paths carrying a single
+    /// wavelength get the grey reflectance at just that wavelength.
+    fn reflectance_rgb(
+        &self,
+        rec: &HitRecord,
+        cos_theta: f64,
+        outer: f64,
+        substrate: Substrate,
+        wavelength: Option<f64>,
+    ) -> Color {
+        let thickness = self.thickness_at(rec);
+        let reflectance =
+            |wavelength| self.reflectance(thickness, wavelength, cos_theta, outer, substrate);
+        match wavelength {
+            Some(wavelength) => {
+                let r = reflectance(wavelength);
+                Color::new(r, r, r)
+            }
+            None => spectrum_to_rgb(reflectance),
+        }
+    }
+
+    /// Unpolarized reflectance at one `wavelength` in nanometres, from the Airy sum of
+    /// the waves bouncing between the film's faces.
+    fn reflectance(
+        &self,
+        thickness: f64,
+        wavelength: f64,
+        cos_theta: f64,
+        outer: f64,
+        substrate: Substrate,
+    ) -> f64 {
+        let film = self.refraction_index;
+        let cos1 = cos_theta.clamp(0.0, 1.0);
+        let sin2_sq = (outer / film).powi(2) * (1.0 - cos1 * cos1);
+        if sin2_sq >= 1.0 {
+            return 1.0;
+        }
+        let cos2 = (1.0 - sin2_sq).sqrt();
+
+        let fresnel = |n1: f64, cos1: f64, n2: f64, cos2: f64| {
+            let s = (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2);
+            let p = (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2);
+            (s, p)
+        };
+        let (r12s, r12p) = fresnel(outer, cos1, film, cos2);
+        let (r23s, r23p) = match substrate {
+            Substrate::Dielectric(relative_index) => {
+                let inner = outer * relative_index;
+                let sin3_sq = (film / inner).powi(2) * sin2_sq;
+                // Nothing gets into the substrate, and the film itself is lossless.
+                if sin3_sq >= 1.0 {
+                    return 1.0;
+                }
+                fresnel(film, cos2, inner, (1.0 - sin3_sq).sqrt())
+            }
+            Substrate::Conductor(albedo) => {
+                let r = -rgb_to_spectrum(&albedo, wavelength).clamp(0.0, 1.0).sqrt();
+                (r, r)
+            }
+        };
+
+        let cos_delta = (4.0 * PI * film * thickness * cos2 / wavelength).cos();
+        let airy = |r12: f64, r23: f64| {
+            let cross = 2.0 * r12 * r23 * cos_delta;
+            (r12 * r12 + r23 * r23 + cross) / (1.0 + r12 * r12 * r23 * r23 + cross)
+        };
+        0.5 * (airy(r12s, r23s) + airy(r12p, r23p))
+    }
+}
+
+unsafe impl Send for ThinFilm {}
+unsafe impl Sync for ThinFilm {}
+
+/// Single scattering albedo that makes a semi-infinite isotropic medium reflect
+/// `albedo` overall, using Chiang et al.'s fit to van de Hulst's tables.
+fn single_scattering_albedo(albedo: f64) -> f64 {
+    let a = albedo.clamp(0.0, 0.999);
+    let s = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+    1.0 - s * s
+}
+
+/// Absorption and scattering coefficients of a participating medium per unit length
+/// (and per unit density, for heterogeneous media). `emission` is the radiance emitted
+/// per unit length, so a thin slab of thickness `l` glows with about `l * emission`.
+#[derive(Clone, Copy, Debug)]
+pub struct MediumCoefficients {
+    pub sigma_a: Color,
+    pub sigma_s: Color,
+    pub emission: Color,
+    pub phase: PhaseFunction,
+}
+
+impl MediumCoefficients {
+    #[inline]
+    pub fn extinction(&self) -> Color {
+        self.sigma_a + self.sigma_s
+    }
+}
+
+unsafe impl Send for MediumCoefficients {}
+unsafe impl Sync for MediumCoefficients {}
+
+/// Angular distribution of light scattered inside a medium, as a function of the angle
+/// between the incoming propagation direction and the outgoing one.
+#[derive(Clone, Copy, Debug)]
+pub enum PhaseFunction {
+    Isotropic,
+    /// `g` in (-1, 1): positive scatters forward, negative backward.
+    HenyeyGreenstein {
+        g: f64,
+    },
+    /// Mix of two lobes, `weight` of `g1` and the rest `g2`. Typically one forward
+    /// and one backward lobe, as in clouds.
+    DoubleHenyeyGreenstein {
+        g1: f64,
+        g2: f64,
+        weight: f64,
+    },
+}
+
+impl PhaseFunction {
+    fn hg(g: f64, cos_theta: f64) -> f64 {
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+    }
+
+    fn sample_hg_cos(g: f64) -> f64 {
+        let xi = random_double();
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+        let sq = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        ((1.0 + g * g - sq * sq) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+
+    /// Value of the phase function, which is also its pdf, for light travelling along
+    /// `dir_in` and leaving along `wi`.
+    pub fn eval(&self, dir_in: &Vec3, wi: &Vec3) -> f64 {
+        let cos_theta = dir_in.unit_vector().dot(&wi.unit_vector());
+        match self {
+            Self::Isotropic => 1.0 / (4.0 * PI),
+            Self::HenyeyGreenstein { g } => Self::hg(*g, cos_theta),
+            Self::DoubleHenyeyGreenstein { g1, g2, weight } => {
+                weight * Self::hg(*g1, cos_theta) + (1.0 - weight) * Self::hg(*g2, cos_theta)
+            }
+        }
+    }
+
+    /// Draws an outgoing unit direction for light travelling along `dir_in`.
+    pub fn sample(&self, dir_in: &Vec3) -> Vec3 {
+        let cos_theta = match self {
+            Self::Isotropic => return Vec3::random_unit_vector(),
+            Self::HenyeyGreenstein { g } => Self::sample_hg_cos(*g),
+            Self::DoubleHenyeyGreenstein { g1, g2, weight } => {
+                if random_double() < *weight {
+                    Self::sample_hg_cos(*g1)
+                } else {
+                    Self::sample_hg_cos(*g2)
+                }
+            }
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_double();
+        let local = Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+
+        ONB::new(dir_in).transform(local)
+    }
+}
+
+unsafe impl Send for PhaseFunction {}
+unsafe impl Sync for PhaseFunction {}
+
+/// Angular distribution of light leaving an emitter, relative to its normal.
+#[derive(Clone, Copy, Debug)]
+pub enum EmissionProfile {
+    /// Equal radiance in every direction.
+    Diffuse,
+    /// Radiance falls off as cos^exponent, focusing the emitter into a beam.
+    CosinePower { exponent: f64 },
+}
+
+impl EmissionProfile {
+    #[inline]
+    pub fn falloff(&self, cos_theta: f64) -> f64 {
+        match self {
+            Self::Diffuse => 1.0,
+            Self::CosinePower { exponent } => cos_theta.max(0.0).powf(*exponent),
+        }
+    }
+
+    /// Emission `scale` that makes an emitter of the given surface `area` with a unit
+    /// texture radiate `watts` in total.
+    pub fn scale_for_power(&self, watts: f64, area: f64, two_sided: bool) -> f64 {
+        // Integral of falloff(cos) * cos over the hemisphere.
+        let projected_solid_angle = match self {
+            Self::Diffuse => PI,
+            Self::CosinePower { exponent } => 2.0 * PI / (exponent + 2.0),
+        };
+        let sides = if two_sided { 2.0 } else { 1.0 };
+        watts / (projected_solid_angle * area * sides)
+    }
+}
+
+unsafe impl Send for EmissionProfile {}
+unsafe impl Sync for EmissionProfile {}
+
+/// The dielectrics a path is currently inside of, innermost last.
+///
+/// When refractive volumes overlap, the one with the highest `priority` defines the
+/// medium; equal priorities resolve to the most recently entered.
+#[derive(Clone, Debug, Default)]
+pub struct MediumStack {
+    media: Vec<Arc<Material>>,
+}
+
+impl MediumStack {
+    pub fn push(&mut self, mat: Arc<Material>) {
+        self.media.push(mat);
+    }
+
+    pub fn remove(&mut self, mat: &Arc<Material>) {
+        if let Some(i) = self.position(mat) {
+            self.media.remove(i);
+        }
+    }
+
+    pub fn contains(&self, mat: &Arc<Material>) -> bool {
+        self.position(mat).is_some()
+    }
+
+    /// Refraction index of the enclosing medium at `wavelength`, ignoring `skip`. Air when
+    /// the stack is empty.
+    pub fn refraction_index(&self, skip: Option<&Arc<Material>>, wavelength: Option<f64>) -> f64 {
+        self.dominant(skip, wavelength)
+            .map_or(1.0, |(refraction_index, _)| refraction_index)
+    }
+
+    /// Whether any medium other than `skip` has a strictly higher priority.
+    pub fn outranks(&self, priority: u32, skip: Option<&Arc<Material>>) -> bool {
+        self.dominant(skip, None).is_some_and(|(_, p)| p > priority)
+    }
+
+    /// The subsurface material the path is inside of, if it is the innermost medium.
+    pub fn subsurface(&self) -> Option<Arc<Material>> {
+        self.media
+            .last()
+            .filter(|mat| matches!(***mat, Material::Subsurface { .. }))
+            .cloned()
+    }
+
+    fn position(&self, mat: &Arc<Material>) -> Option<usize> {
+        self.media.iter().rposition(|m| Arc::ptr_eq(m, mat))
+    }
+
+    fn dominant(
+        &self,
+        skip: Option<&Arc<Material>>,
+        wavelength: Option<f64>,
+    ) -> Option<(f64, u32)> {
+        let skipped = skip.and_then(|mat| self.position(mat));
+
+        self.media
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| Some(i) != skipped)
+            .filter_map(|(_, mat)| mat.interface(wavelength))
+            .fold(None, |best, (refraction_index, priority)| match best {
+                Some((_, p)) if p > priority => best,
+                _ => Some((refraction_index, priority)),
+            })
+    }
+}
@@ -0,0 +1,33 @@
+use crate::vec3::Vec3;
+
+/// Orthonormal basis with `w` along a given axis.
+#[derive(Clone, Copy, Debug)]
+pub struct ONB {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl ONB {
+    pub fn new(n: &Vec3) -> Self {
+        let w = n.unit_vector();
+        let a = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(&a).unit_vector();
+        let u = w.cross(&v);
+
+        Self { u, v, w }
+    }
+
+    /// Maps a vector expressed in this basis back to world space.
+    #[inline]
+    pub fn transform(&self, v: Vec3) -> Vec3 {
+        v.x * self.u + v.y * self.v + v.z * self.w
+    }
+}
+
+unsafe impl Send for ONB {}
+unsafe impl Sync for ONB {}
@@ -1,7 +1,56 @@
-use crate::{
-    utils::random_int,
-    vec3::{Point3, Vec3},
-};
+use std::f64::consts::FRAC_1_SQRT_2;
+
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::vec3::{Point3, Vec3};
+
+/// Octave settings shared by the fractal sums: each octave multiplies the frequency by
+/// `lacunarity` and the amplitude by `gain`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fractal {
+    pub octaves: i32,
+    pub lacunarity: f64,
+    pub gain: f64,
+}
+
+impl Fractal {
+    pub fn new(octaves: i32, lacunarity: f64, gain: f64) -> Self {
+        Self {
+            octaves,
+            lacunarity,
+            gain,
+        }
+    }
+
+    /// The same settings limited to the octaves whose period is still longer than
+    /// `width`, for a pattern evaluated at frequency `frequency`.
+    pub fn band_limited(&self, frequency: f64, width: f64) -> Self {
+        let finest = frequency * width;
+        if finest <= 0.0 || self.lacunarity <= 1.0 {
+            return *self;
+        }
+        let octaves = 1 + (-finest.ln() / self.lacunarity.ln()).floor() as i32;
+        Self {
+            octaves: octaves.clamp(1, self.octaves.max(1)),
+            ..*self
+        }
+    }
+
+    fn amplitude_sum(&self) -> f64 {
+        let mut amplitude = 1.0;
+        (0..self.octaves.max(1)).fold(0.0, |acc, _| {
+            let acc = acc + amplitude;
+            amplitude *= self.gain;
+            acc
+        })
+    }
+}
+
+impl Default for Fractal {
+    fn default() -> Self {
+        Self::new(6, 2.0, 0.5)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Perlin {
@@ -9,24 +58,60 @@ pub struct Perlin {
     perm_x: Vec<usize>,
     perm_y: Vec<usize>,
     perm_z: Vec<usize>,
+    perm_w: Vec<usize>,
+    period: i32,
 }
 
 impl Perlin {
+    const POINT_COUNT: usize = 256;
+
     pub fn new() -> Self {
-        let mut randvec = Vec::with_capacity(256);
+        Self::from_rng(&mut thread_rng(), Self::POINT_COUNT as i32)
+    }
 
-        for _ in 0..256 {
-            randvec.push(Vec3::random_range(-1.0, 1.0).unit_vector());
-        }
+    /// Noise that is identical for every run with the same `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::from_rng(&mut StdRng::seed_from_u64(seed), Self::POINT_COUNT as i32)
+    }
+
+    /// Seeded noise that repeats every `period` lattice units along each axis, time
+    /// included, clamped to 1..=256. Fractal sums with integer lacunarity tile with the
+    /// same period, so a texture at scale `s` tiles every `period / s` units.
+    pub fn with_period(seed: u64, period: i32) -> Self {
+        Self::from_rng(
+            &mut StdRng::seed_from_u64(seed),
+            period.clamp(1, Self::POINT_COUNT as i32),
+        )
+    }
+
+    fn from_rng(rng: &mut impl Rng, period: i32) -> Self {
+        let randvec = (0..Self::POINT_COUNT)
+            .map(|_| {
+                Vec3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .unit_vector()
+            })
+            .collect();
 
         Self {
             randvec,
-            perm_x: Perlin::perlin_generate_perm(),
-            perm_y: Perlin::perlin_generate_perm(),
-            perm_z: Perlin::perlin_generate_perm(),
+            perm_x: Perlin::perlin_generate_perm(rng),
+            perm_y: Perlin::perlin_generate_perm(rng),
+            perm_z: Perlin::perlin_generate_perm(rng),
+            perm_w: Perlin::perlin_generate_perm(rng),
+            period,
         }
     }
 
+    /// Lattice coordinate `i` wrapped to the period, as a permutation table index.
+    #[inline]
+    fn lattice(&self, i: i32) -> usize {
+        i.rem_euclid(self.period) as usize
+    }
+
     pub fn noise(&self, p: Point3) -> f64 {
         let u = p.x - p.x.floor();
         let v = p.y - p.y.floor();
@@ -41,9 +126,9 @@ impl Perlin {
         for (di, x) in c.iter_mut().enumerate() {
             for (dj, y) in x.iter_mut().enumerate() {
                 for (dk, z) in y.iter_mut().enumerate() {
-                    *z = self.randvec[self.perm_x[((i + di as i32) & 255) as usize]
-                        ^ self.perm_y[((j + dj as i32) & 255) as usize]
-                        ^ self.perm_z[((k + dk as i32) & 255) as usize]];
+                    *z = self.randvec[self.perm_x[self.lattice(i + di as i32)]
+                        ^ self.perm_y[self.lattice(j + dj as i32)]
+                        ^ self.perm_z[self.lattice(k + dk as i32)]];
                 }
             }
         }
@@ -51,6 +136,75 @@ impl Perlin {
         Perlin::perlin_interp(c, u, v, w)
     }
 
+    /// Noise over the plane, with gradients from the eight compass directions.
+    pub fn noise_2d(&self, x: f64, y: f64) -> f64 {
+        const GRADIENTS: [(f64, f64); 8] = [
+            (1.0, 0.0),
+            (-1.0, 0.0),
+            (0.0, 1.0),
+            (0.0, -1.0),
+            (FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+            (-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+            (FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+            (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+        ];
+
+        let (i, j) = (x.floor() as i32, y.floor() as i32);
+        let (u, v) = (x - x.floor(), y - y.floor());
+        let (uu, vv) = (Self::fade(u), Self::fade(v));
+
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                let hash = self.perm_x[self.lattice(i + di)] ^ self.perm_y[self.lattice(j + dj)];
+                let (gx, gy) = GRADIENTS[hash & 7];
+                let (fi, fj) = (di as f64, dj as f64);
+                accum += (fi * uu + (1. - fi) * (1. - uu))
+                    * (fj * vv + (1. - fj) * (1. - vv))
+                    * (gx * (u - fi) + gy * (v - fj));
+            }
+        }
+
+        accum
+    }
+
+    /// Noise over space and time, so patterns evolve smoothly when `t` is animated
+    /// instead of sliding. Gradients are the 32 edge midpoints of the 4D hypercube.
+    pub fn noise_4d(&self, p: Point3, t: f64) -> f64 {
+        let q = [p.x, p.y, p.z, t];
+        let cell = q.map(|x| x.floor() as i32);
+        let frac: [f64; 4] = std::array::from_fn(|a| q[a] - q[a].floor());
+        let faded = frac.map(Self::fade);
+
+        let mut accum = 0.0;
+        for corner in 0..16 {
+            let offset: [i32; 4] = std::array::from_fn(|a| (corner >> a) & 1);
+            let hash = self.perm_x[self.lattice(cell[0] + offset[0])]
+                ^ self.perm_y[self.lattice(cell[1] + offset[1])]
+                ^ self.perm_z[self.lattice(cell[2] + offset[2])]
+                ^ self.perm_w[self.lattice(cell[3] + offset[3])];
+
+            // Zero on axis `hash / 8`, signs of the other three from the low bits.
+            let zero = (hash >> 3) & 3;
+            let mut bit = 0;
+            let mut dot = 0.0;
+            let mut weight = 1.0;
+            for a in 0..4 {
+                let o = offset[a] as f64;
+                let d = frac[a] - o;
+                weight *= o * faded[a] + (1. - o) * (1. - faded[a]);
+                if a != zero {
+                    dot += if (hash >> bit) & 1 == 0 { d } else { -d };
+                    bit += 1;
+                }
+            }
+            accum += weight * dot;
+        }
+
+        // The unnormalised edge gradients have length sqrt(3).
+        accum / 3f64.sqrt()
+    }
+
     pub fn turb(&self, p: Point3, depth: i32) -> f64 {
         let mut temp_p = p;
         let mut weight = 1.0;
@@ -65,28 +219,111 @@ impl Perlin {
             .abs()
     }
 
-    fn perlin_generate_perm() -> Vec<usize> {
-        let mut p = Vec::with_capacity(256);
-        for i in 0..256 {
+    /// Fractal Brownian motion, normalised by the total amplitude to roughly [-1, 1].
+    pub fn fbm(&self, p: Point3, fractal: &Fractal) -> f64 {
+        self.octave_sum(fractal, |f| self.noise(f * p), |n| n)
+    }
+
+    /// Like `fbm` but summing absolute values, giving billowy [0, 1] turbulence.
+    pub fn turbulence(&self, p: Point3, fractal: &Fractal) -> f64 {
+        self.octave_sum(fractal, |f| self.noise(f * p), f64::abs)
+    }
+
+    /// Musgrave's ridged multifractal in [0, 1]: sharp crests where the noise crosses
+    /// zero, with each octave weighted by the one before so valleys stay smooth.
+    pub fn ridged(&self, p: Point3, fractal: &Fractal) -> f64 {
+        self.ridged_sum(fractal, |f| self.noise(f * p))
+    }
+
+    /// `fbm` evolving with time `t`; the time axis is scaled with each octave too.
+    pub fn fbm_4d(&self, p: Point3, t: f64, fractal: &Fractal) -> f64 {
+        self.octave_sum(fractal, |f| self.noise_4d(f * p, f * t), |n| n)
+    }
+
+    pub fn turbulence_4d(&self, p: Point3, t: f64, fractal: &Fractal) -> f64 {
+        self.octave_sum(fractal, |f| self.noise_4d(f * p, f * t), f64::abs)
+    }
+
+    pub fn ridged_4d(&self, p: Point3, t: f64, fractal: &Fractal) -> f64 {
+        self.ridged_sum(fractal, |f| self.noise_4d(f * p, f * t))
+    }
+
+    /// Displaces `p` by a vector of three decorrelated fBm lookups scaled by `strength`,
+    /// the domain warping behind swirled and streaked patterns.
+    pub fn warp(&self, p: Point3, strength: f64, fractal: &Fractal) -> Point3 {
+        let offset = Vec3::new(
+            self.fbm(p, fractal),
+            self.fbm(p + Vec3::new(5.2, 1.3, 7.1), fractal),
+            self.fbm(p + Vec3::new(1.7, 9.2, 3.4), fractal),
+        );
+        p + strength * offset
+    }
+
+    /// Sums `shape(sample(frequency))` over the octaves, normalised by the amplitudes.
+    fn octave_sum(
+        &self,
+        fractal: &Fractal,
+        sample: impl Fn(f64) -> f64,
+        shape: impl Fn(f64) -> f64,
+    ) -> f64 {
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+
+        let sum = (0..fractal.octaves.max(1)).fold(0.0, |acc, _| {
+            let acc = acc + amplitude * shape(sample(frequency));
+            amplitude *= fractal.gain;
+            frequency *= fractal.lacunarity;
+            acc
+        });
+        sum / fractal.amplitude_sum()
+    }
+
+    fn ridged_sum(&self, fractal: &Fractal, sample: impl Fn(f64) -> f64) -> f64 {
+        const OFFSET: f64 = 1.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut weight = 1.0;
+        let mut sum = 0.0;
+
+        for _ in 0..fractal.octaves.max(1) {
+            let signal = OFFSET - sample(frequency).abs();
+            let signal = signal * signal * weight;
+            weight = (2.0 * signal).clamp(0.0, 1.0);
+            sum += amplitude * signal;
+            amplitude *= fractal.gain;
+            frequency *= fractal.lacunarity;
+        }
+
+        sum / fractal.amplitude_sum()
+    }
+
+    fn perlin_generate_perm(rng: &mut impl Rng) -> Vec<usize> {
+        let mut p = Vec::with_capacity(Self::POINT_COUNT);
+        for i in 0..Self::POINT_COUNT {
             p.push(i);
         }
 
-        Perlin::permute(&mut p, 256);
+        Perlin::permute(&mut p, Self::POINT_COUNT, rng);
 
         p
     }
 
-    fn permute(p: &mut [usize], n: usize) {
+    fn permute(p: &mut [usize], n: usize, rng: &mut impl Rng) {
         for i in (0..n).rev() {
-            let target = random_int(0, i as i32) as usize;
+            let target = rng.gen_range(0..=i);
             p.swap(i, target);
         }
     }
 
+    #[inline]
+    fn fade(t: f64) -> f64 {
+        t * t * (3. - 2. * t)
+    }
+
     fn perlin_interp(c: [[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
-        let uu = u * u * (3. - 2. * u);
-        let vv = v * v * (3. - 2. * v);
-        let ww = w * w * (3. - 2. * w);
+        let uu = Self::fade(u);
+        let vv = Self::fade(v);
+        let ww = Self::fade(w);
         let mut accum = 0.0;
 
         for (i, x) in c.iter().enumerate() {
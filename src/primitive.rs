@@ -1,13 +1,18 @@
+use std::collections::HashMap;
 use std::f64::consts::PI;
 use std::fmt::Debug;
 // use std::rc::Rc;
 use std::sync::Arc;
 
-use crate::aabb::AABB;
+use crate::aabb::{AABB, EMPTY};
+use crate::bvh::BVHNode;
+use crate::color::{luminance, Color};
+use crate::density::DensityField;
 use crate::interval::{Interval, UNIVERSE};
-use crate::material::Material;
+use crate::material::{Material, MediumCoefficients, PhaseFunction};
+use crate::onb::ONB;
 use crate::ray::Ray;
-use crate::texture::Texture;
+use crate::texture::{Footprint, ObjectSpace, Texture};
 use crate::utils::{degrees_to_radians, random_double};
 use crate::vec3::*;
 
@@ -20,11 +25,88 @@ pub struct HitRecord {
     pub u: f64,
     pub v: f64,
     pub front_face: bool,
+    /// Partial derivatives of the position and of the normal with respect to (u, v),
+    /// zero where the surface has no parameterization.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    pub dndu: Vec3,
+    pub dndv: Vec3,
+    /// Filled in by the camera from the ray's differentials, if it has any.
+    pub footprint: Footprint,
+    /// The hit in the space of the object, set by instance transforms.
+    pub object: Option<ObjectSpace>,
+}
+
+impl HitRecord {
+    /// Estimates how much the hit's position and texture coordinates change per pixel,
+    /// by intersecting `r`'s offset rays with the tangent plane. The plane comes from the
+    /// partials where there are any, so a bumped shading normal does not tilt it.
+    pub fn set_footprint(&mut self, r: &Ray) {
+        self.footprint = Footprint {
+            normal: self.normal,
+            object: self.object,
+            ..Footprint::default()
+        };
+        let Some(diff) = r.differentials() else {
+            return;
+        };
+
+        let n = match self.dpdu.cross(&self.dpdv) {
+            n if n.near_zero() => self.normal,
+            n => n,
+        };
+        let d = n.dot(&self.p);
+        let tx = (d - n.dot(&diff.rx_origin)) / n.dot(&diff.rx_direction);
+        let ty = (d - n.dot(&diff.ry_origin)) / n.dot(&diff.ry_direction);
+        if !tx.is_finite() || !ty.is_finite() {
+            return;
+        }
+        let dpdx = diff.rx_origin + tx * diff.rx_direction - self.p;
+        let dpdy = diff.ry_origin + ty * diff.ry_direction - self.p;
+
+        // Least squares solution of dp = dpdu * du + dpdv * dv.
+        let ata00 = self.dpdu.dot(&self.dpdu);
+        let ata01 = self.dpdu.dot(&self.dpdv);
+        let ata11 = self.dpdv.dot(&self.dpdv);
+        let det = ata00 * ata11 - ata01 * ata01;
+        let solve = |dp: &Vec3| {
+            if det.abs() < 1e-12 {
+                return (0.0, 0.0);
+            }
+            let (b0, b1) = (self.dpdu.dot(dp), self.dpdv.dot(dp));
+            let du = (ata11 * b0 - ata01 * b1) / det;
+            let dv = (ata00 * b1 - ata01 * b0) / det;
+            (du.clamp(-1e8, 1e8), dv.clamp(-1e8, 1e8))
+        };
+        let (dudx, dvdx) = solve(&dpdx);
+        let (dudy, dvdy) = solve(&dpdy);
+
+        self.footprint = Footprint {
+            dpdx,
+            dpdy,
+            dudx,
+            dvdx,
+            dudy,
+            dvdy,
+            normal: self.normal,
+            object: self.object,
+        };
+    }
 }
 
 pub trait Hittable: Debug {
     fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord>;
     fn bounding_box(&self) -> AABB;
+
+    /// Fraction of light that makes it along `r` over `ray_t`. Any surface blocks it all;
+    /// participating media attenuate it.
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        if self.hit(r, &mut ray_t.clone()).is_some() {
+            Color::default()
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -68,6 +150,10 @@ impl Sphere {
         }
     }
 
+    pub fn area(&self) -> f64 {
+        4.0 * PI * self.radius * self.radius
+    }
+
     fn sphere_center(&self, time: f64) -> Point3 {
         self.center1 + time * self.center_vec
     }
@@ -78,6 +164,16 @@ impl Sphere {
 
         (phi / (2.0 * PI), theta / PI)
     }
+
+    /// Derivatives of the point with the unit outward normal `n` by the coordinates of
+    /// `get_sphere_uv`, nudged away from the poles where dpdu vanishes.
+    fn get_sphere_partials(&self, n: Point3) -> (Vec3, Vec3) {
+        let sin_theta = (n.x * n.x + n.z * n.z).sqrt().max(1e-8);
+        let dpdu = 2.0 * PI * self.radius * Vec3::new(n.z, 0.0, -n.x);
+        let dpdv =
+            PI * self.radius * Vec3::new(-n.x * n.y / sin_theta, sin_theta, -n.z * n.y / sin_theta);
+        (dpdu, dpdv)
+    }
 }
 
 impl Hittable for Sphere {
@@ -107,11 +203,17 @@ impl Hittable for Sphere {
         }
         let t = root;
         let p = r.at(t);
-        let normal = (p - center) / self.radius;
-        let front_face = r.direction().dot(&normal) < 0.0;
-        let normal = if front_face { normal } else { -normal };
+        let outward_normal = (p - center) / self.radius;
+        let front_face = r.direction().dot(&outward_normal) < 0.0;
+        let normal = if front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
         let mat = self.mat.clone();
-        let (u, v) = self.get_sphere_uv(normal);
+        let (u, v) = self.get_sphere_uv(outward_normal);
+        let (dpdu, dpdv) = self.get_sphere_partials(outward_normal);
+        let sign = if front_face { 1.0 } else { -1.0 };
 
         Some(HitRecord {
             t,
@@ -121,6 +223,12 @@ impl Hittable for Sphere {
             front_face,
             u,
             v,
+            dpdu,
+            dpdv,
+            dndu: (sign / self.radius) * dpdu,
+            dndv: (sign / self.radius) * dpdv,
+            footprint: Footprint::default(),
+            object: None,
         })
     }
 
@@ -169,6 +277,17 @@ impl Hittable for HittableList {
             .1 // Returning curr_rec
     }
 
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        let mut tr = Color::new(1.0, 1.0, 1.0);
+        for object in &self.objects {
+            tr = tr * object.transmittance(r, ray_t);
+            if tr.max_component() <= 0.0 {
+                return Color::default();
+            }
+        }
+        tr
+    }
+
     fn bounding_box(&self) -> AABB {
         self.bbox
     }
@@ -221,6 +340,15 @@ impl Planar {
         planar
     }
 
+    pub fn area(&self) -> f64 {
+        let parallelogram = self.u.cross(&self.v).length();
+        match self.shape {
+            Shape::Quad => parallelogram,
+            Shape::Triangle => 0.5 * parallelogram,
+            Shape::Circle { radius } => PI * radius * radius * parallelogram,
+        }
+    }
+
     pub fn set_bounding_box(&mut self) {
         let bbox_diagonal1 = AABB::from((self.q, self.q + self.u + self.v));
         let bbox_diagonal2 = AABB::from((self.q + self.u, self.q + self.v));
@@ -283,6 +411,12 @@ impl Hittable for Planar {
             front_face,
             u: alpha,
             v: beta,
+            dpdu: self.u,
+            dpdv: self.v,
+            dndu: Vec3::default(),
+            dndv: Vec3::default(),
+            footprint: Footprint::default(),
+            object: None,
         })
     }
 
@@ -294,6 +428,689 @@ impl Hittable for Planar {
 unsafe impl Send for Planar {}
 unsafe impl Sync for Planar {}
 
+/// Triangle with per-vertex shading normals and texture coordinates, the building block
+/// of tessellated surfaces.
+#[derive(Clone, Debug)]
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: [Vec3; 3],
+    uvs: [(f64, f64); 3],
+    mat: Arc<Material>,
+    bbox: AABB,
+}
+
+impl Triangle {
+    /// Flat triangle with coordinates (0, 0), (1, 0) and (0, 1) at its corners, like a
+    /// `Planar` triangle.
+    pub fn new(vertices: [Point3; 3], mat: Arc<Material>) -> Self {
+        let normal = (vertices[1] - vertices[0])
+            .cross(&(vertices[2] - vertices[0]))
+            .unit_vector();
+        Self::with_attributes(
+            vertices,
+            [normal; 3],
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+            mat,
+        )
+    }
+
+    /// Normals should lie on the side the vertices wind counterclockwise around.
+    pub fn with_attributes(
+        vertices: [Point3; 3],
+        normals: [Vec3; 3],
+        uvs: [(f64, f64); 3],
+        mat: Arc<Material>,
+    ) -> Self {
+        let bbox = AABB::from((
+            AABB::from((vertices[0], vertices[1])),
+            AABB::from((vertices[2], vertices[2])),
+        ));
+        Self {
+            vertices,
+            normals,
+            uvs,
+            mat,
+            bbox,
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let (t, b1, b2) = intersect_triangle(r, &self.vertices)?;
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        let [p0, p1, p2] = self.vertices;
+        let (e1, e2) = (p1 - p0, p2 - p0);
+        let b0 = 1.0 - b1 - b2;
+        let geometric = e1.cross(&e2).unit_vector();
+        let front_face = r.direction().dot(&geometric) < 0.0;
+        let sign = if front_face { 1.0 } else { -1.0 };
+
+        let shading = b0 * self.normals[0] + b1 * self.normals[1] + b2 * self.normals[2];
+        let shading = match shading {
+            n if n.near_zero() => geometric,
+            n if n.dot(&geometric) < 0.0 => -n.unit_vector(),
+            n => n.unit_vector(),
+        };
+
+        let u = b0 * self.uvs[0].0 + b1 * self.uvs[1].0 + b2 * self.uvs[2].0;
+        let v = b0 * self.uvs[0].1 + b1 * self.uvs[1].1 + b2 * self.uvs[2].1;
+        let (dpdu, dpdv) = uv_partials(self.uvs, self.vertices).unwrap_or((e1, e2));
+        let (dndu, dndv) =
+            uv_partials(self.uvs, self.normals).unwrap_or((Vec3::default(), Vec3::default()));
+
+        Some(HitRecord {
+            t,
+            p: b0 * p0 + b1 * p1 + b2 * p2,
+            normal: sign * shading,
+            mat: self.mat.clone(),
+            front_face,
+            u,
+            v,
+            dpdu,
+            dpdv,
+            dndu: sign * dndu,
+            dndv: sign * dndv,
+            footprint: Footprint::default(),
+            object: None,
+        })
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+unsafe impl Send for Triangle {}
+unsafe impl Sync for Triangle {}
+
+/// Moller-Trumbore ray-triangle intersection, giving the ray parameter and the
+/// barycentric weights of the second and third vertices.
+pub(crate) fn intersect_triangle(r: &Ray, vertices: &[Point3; 3]) -> Option<(f64, f64, f64)> {
+    let [p0, p1, p2] = *vertices;
+    let (e1, e2) = (p1 - p0, p2 - p0);
+    let pvec = r.direction().cross(&e2);
+    let det = e1.dot(&pvec);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = r.origin() - p0;
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(&e1);
+    let b2 = r.direction().dot(&qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    Some((e2.dot(&qvec) * inv_det, b1, b2))
+}
+
+/// Derivatives by texture coordinates of `values` given at the corners of a triangle
+/// with coordinates `uvs`, or `None` where the coordinates are degenerate.
+pub(crate) fn uv_partials(uvs: [(f64, f64); 3], values: [Vec3; 3]) -> Option<(Vec3, Vec3)> {
+    let (du1, dv1) = (uvs[1].0 - uvs[0].0, uvs[1].1 - uvs[0].1);
+    let (du2, dv2) = (uvs[2].0 - uvs[0].0, uvs[2].1 - uvs[0].1);
+    let det = du1 * dv2 - dv1 * du2;
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let (d1, d2) = (values[1] - values[0], values[2] - values[0]);
+    Some(((dv2 * d1 - dv1 * d2) / det, (du1 * d2 - du2 * d1) / det))
+}
+
+/// Placement of a shape modelled around its local z axis, with `origin` at the local
+/// origin and the axis along `onb.w`.
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    origin: Point3,
+    onb: ONB,
+}
+
+/// A hit in a `Frame`'s local coordinates, with the outward normal.
+struct LocalHit {
+    t: f64,
+    p: Point3,
+    normal: Vec3,
+    u: f64,
+    v: f64,
+    dpdu: Vec3,
+    dpdv: Vec3,
+    dndu: Vec3,
+    dndv: Vec3,
+}
+
+impl Frame {
+    fn new(origin: Point3, axis: Vec3) -> Self {
+        Self {
+            origin,
+            onb: ONB::new(&axis),
+        }
+    }
+
+    fn local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(&self.onb.u), v.dot(&self.onb.v), v.dot(&self.onb.w))
+    }
+
+    /// The ray's origin and direction in local coordinates.
+    fn ray_to_local(&self, r: &Ray) -> (Point3, Vec3) {
+        (
+            self.local(r.origin() - self.origin),
+            self.local(r.direction()),
+        )
+    }
+
+    /// World bounds of the local box from `min` to `max`.
+    fn bounding_box(&self, min: Point3, max: Point3) -> AABB {
+        (0..8).fold(EMPTY, |bbox, corner| {
+            let local = Point3::new(
+                if corner & 1 == 0 { min.x } else { max.x },
+                if corner & 2 == 0 { min.y } else { max.y },
+                if corner & 4 == 0 { min.z } else { max.z },
+            );
+            let p = self.origin + self.onb.transform(local);
+            AABB::from((bbox, AABB::from((p, p))))
+        })
+    }
+
+    fn record(&self, r: &Ray, hit: LocalHit, mat: &Arc<Material>) -> HitRecord {
+        let outward_normal = self.onb.transform(hit.normal).unit_vector();
+        let front_face = r.direction().dot(&outward_normal) < 0.0;
+        let sign = if front_face { 1.0 } else { -1.0 };
+
+        HitRecord {
+            t: hit.t,
+            p: self.origin + self.onb.transform(hit.p),
+            normal: sign * outward_normal,
+            mat: mat.clone(),
+            front_face,
+            u: hit.u,
+            v: hit.v,
+            dpdu: self.onb.transform(hit.dpdu),
+            dpdv: self.onb.transform(hit.dpdv),
+            dndu: sign * self.onb.transform(hit.dndu),
+            dndv: sign * self.onb.transform(hit.dndv),
+            footprint: Footprint::default(),
+            object: None,
+        }
+    }
+}
+
+/// Angle of (x, y) around the local axis as a fraction of a turn in [0, 1).
+#[inline]
+fn turn_fraction(x: f64, y: f64) -> f64 {
+    let phi = y.atan2(x);
+    (if phi < 0.0 { phi + 2.0 * PI } else { phi }) / (2.0 * PI)
+}
+
+/// Hit with the annulus between `inner` and `outer` in the plane z = `z`, facing +z or
+/// -z by `facing`. `u` runs around the axis and `v` from the outer edge inwards.
+fn annulus_hit(
+    o: Point3,
+    d: Vec3,
+    ray_t: &Interval,
+    z: f64,
+    inner: f64,
+    outer: f64,
+    facing: f64,
+) -> Option<LocalHit> {
+    if d.z == 0.0 {
+        return None;
+    }
+    let t = (z - o.z) / d.z;
+    if !ray_t.surrounds(t) {
+        return None;
+    }
+    let p = o + t * d;
+    let r2 = p.x * p.x + p.y * p.y;
+    if r2 > outer * outer || r2 < inner * inner {
+        return None;
+    }
+
+    let r = r2.sqrt().max(1e-12);
+    let span = (outer - inner).max(1e-12);
+    Some(LocalHit {
+        t,
+        p,
+        normal: Vec3::new(0.0, 0.0, facing),
+        u: turn_fraction(p.x, p.y),
+        v: (outer - r) / span,
+        dpdu: 2.0 * PI * Vec3::new(-p.y, p.x, 0.0),
+        dpdv: (-span / r) * Vec3::new(p.x, p.y, 0.0),
+        dndu: Vec3::default(),
+        dndv: Vec3::default(),
+    })
+}
+
+/// Roots of a * t^2 + 2 * h * t + c in increasing order.
+fn quadratic_roots(a: f64, h: f64, c: f64) -> Option<[f64; 2]> {
+    if a.abs() < 1e-12 {
+        if h.abs() < 1e-12 {
+            return None;
+        }
+        let t = -c / (2.0 * h);
+        return Some([t, t]);
+    }
+    let discriminant = h * h - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrtd = discriminant.sqrt();
+    let (t0, t1) = ((-h - sqrtd) / a, (-h + sqrtd) / a);
+    Some(if t0 <= t1 { [t0, t1] } else { [t1, t0] })
+}
+
+/// Closest of the candidate hits.
+fn closest(hits: impl IntoIterator<Item = Option<LocalHit>>) -> Option<LocalHit> {
+    hits.into_iter()
+        .flatten()
+        .min_by(|a, b| a.t.total_cmp(&b.t))
+}
+
+/// Cylinder of `radius` around the segment from `base` to `base + axis`, open or closed
+/// by disks at both ends. `u` runs around the axis and `v` along it.
+#[derive(Clone, Debug)]
+pub struct Cylinder {
+    frame: Frame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    mat: Arc<Material>,
+    bbox: AABB,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, capped: bool, mat: Arc<Material>) -> Self {
+        let frame = Frame::new(base, axis);
+        let (radius, height) = (radius.max(0.0), axis.length());
+        Self {
+            frame,
+            radius,
+            height,
+            capped,
+            mat,
+            bbox: frame.bounding_box(
+                Point3::new(-radius, -radius, 0.0),
+                Point3::new(radius, radius, height),
+            ),
+        }
+    }
+
+    fn side_hit(&self, o: Point3, d: Vec3, ray_t: &Interval) -> Option<LocalHit> {
+        let a = d.x * d.x + d.y * d.y;
+        let h = o.x * d.x + o.y * d.y;
+        let c = o.x * o.x + o.y * o.y - self.radius * self.radius;
+        let roots = quadratic_roots(a, h, c)?;
+
+        roots.into_iter().find_map(|t| {
+            let p = o + t * d;
+            if !ray_t.surrounds(t) || p.z < 0.0 || p.z > self.height {
+                return None;
+            }
+            let normal = Vec3::new(p.x, p.y, 0.0) / self.radius;
+            let dpdu = 2.0 * PI * Vec3::new(-p.y, p.x, 0.0);
+            Some(LocalHit {
+                t,
+                p,
+                normal,
+                u: turn_fraction(p.x, p.y),
+                v: p.z / self.height,
+                dpdu,
+                dpdv: Vec3::new(0.0, 0.0, self.height),
+                dndu: dpdu / self.radius,
+                dndv: Vec3::default(),
+            })
+        })
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let (o, d) = self.frame.ray_to_local(r);
+        let hit = if self.capped {
+            closest([
+                self.side_hit(o, d, ray_t),
+                annulus_hit(o, d, ray_t, 0.0, 0.0, self.radius, -1.0),
+                annulus_hit(o, d, ray_t, self.height, 0.0, self.radius, 1.0),
+            ])
+        } else {
+            self.side_hit(o, d, ray_t)
+        }?;
+        Some(self.frame.record(r, hit, &self.mat))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+unsafe impl Send for Cylinder {}
+unsafe impl Sync for Cylinder {}
+
+/// Cone with a base of `radius` around `base` and its apex at `base + axis`, optionally
+/// closed by a disk at the base. `u` runs around the axis and `v` from base to apex.
+#[derive(Clone, Debug)]
+pub struct Cone {
+    frame: Frame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    mat: Arc<Material>,
+    bbox: AABB,
+}
+
+impl Cone {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, capped: bool, mat: Arc<Material>) -> Self {
+        let frame = Frame::new(base, axis);
+        let (radius, height) = (radius.max(0.0), axis.length());
+        Self {
+            frame,
+            radius,
+            height,
+            capped,
+            mat,
+            bbox: frame.bounding_box(
+                Point3::new(-radius, -radius, 0.0),
+                Point3::new(radius, radius, height),
+            ),
+        }
+    }
+
+    fn side_hit(&self, o: Point3, d: Vec3, ray_t: &Interval) -> Option<LocalHit> {
+        // x^2 + y^2 = (k * (height - z))^2 with k the radius shrinking per unit height.
+        let k2 = (self.radius / self.height).powi(2);
+        let rise = self.height - o.z;
+        let a = d.x * d.x + d.y * d.y - k2 * d.z * d.z;
+        let h = o.x * d.x + o.y * d.y + k2 * rise * d.z;
+        let c = o.x * o.x + o.y * o.y - k2 * rise * rise;
+        let roots = quadratic_roots(a, h, c)?;
+
+        roots.into_iter().find_map(|t| {
+            let p = o + t * d;
+            if !ray_t.surrounds(t) || p.z < 0.0 || p.z > self.height {
+                return None;
+            }
+            let u = turn_fraction(p.x, p.y);
+            let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+            let normal = Vec3::new(cos_phi, sin_phi, self.radius / self.height).unit_vector();
+            Some(LocalHit {
+                t,
+                p,
+                normal,
+                u,
+                v: p.z / self.height,
+                dpdu: 2.0 * PI * Vec3::new(-p.y, p.x, 0.0),
+                dpdv: Vec3::new(-self.radius * cos_phi, -self.radius * sin_phi, self.height),
+                dndu: 2.0 * PI * Vec3::new(-normal.y, normal.x, 0.0),
+                dndv: Vec3::default(),
+            })
+        })
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let (o, d) = self.frame.ray_to_local(r);
+        let hit = if self.capped {
+            closest([
+                self.side_hit(o, d, ray_t),
+                annulus_hit(o, d, ray_t, 0.0, 0.0, self.radius, -1.0),
+            ])
+        } else {
+            self.side_hit(o, d, ray_t)
+        }?;
+        Some(self.frame.record(r, hit, &self.mat))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+unsafe impl Send for Cone {}
+unsafe impl Sync for Cone {}
+
+/// Flat ring between `inner_radius` and `radius` around `center`, facing along `normal`;
+/// an `inner_radius` of zero gives a full disk. `u` runs around the center and `v` from
+/// the outer edge inwards.
+#[derive(Clone, Debug)]
+pub struct Disk {
+    frame: Frame,
+    radius: f64,
+    inner_radius: f64,
+    mat: Arc<Material>,
+    bbox: AABB,
+}
+
+impl Disk {
+    pub fn new(
+        center: Point3,
+        normal: Vec3,
+        radius: f64,
+        inner_radius: f64,
+        mat: Arc<Material>,
+    ) -> Self {
+        let frame = Frame::new(center, normal);
+        let radius = radius.max(0.0);
+        Self {
+            frame,
+            radius,
+            inner_radius: inner_radius.clamp(0.0, radius),
+            mat,
+            bbox: frame.bounding_box(
+                Point3::new(-radius, -radius, 0.0),
+                Point3::new(radius, radius, 0.0),
+            ),
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let (o, d) = self.frame.ray_to_local(r);
+        let hit = annulus_hit(o, d, ray_t, 0.0, self.inner_radius, self.radius, 1.0)?;
+        Some(self.frame.record(r, hit, &self.mat))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+unsafe impl Send for Disk {}
+unsafe impl Sync for Disk {}
+
+/// Torus around `center` whose tube of `minor_radius` follows a circle of
+/// `major_radius` about `axis`. `u` runs around the axis and `v` around the tube.
+#[derive(Clone, Debug)]
+pub struct Torus {
+    frame: Frame,
+    major_radius: f64,
+    minor_radius: f64,
+    mat: Arc<Material>,
+    bbox: AABB,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        mat: Arc<Material>,
+    ) -> Self {
+        let frame = Frame::new(center, axis);
+        let (major_radius, minor_radius) = (major_radius.max(0.0), minor_radius.max(0.0));
+        let extent = major_radius + minor_radius;
+        Self {
+            frame,
+            major_radius,
+            minor_radius,
+            mat,
+            bbox: frame.bounding_box(
+                Point3::new(-extent, -extent, -minor_radius),
+                Point3::new(extent, extent, minor_radius),
+            ),
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let (o, d) = self.frame.ray_to_local(r);
+        let speed = d.length();
+        if speed == 0.0 {
+            return None;
+        }
+        let d = d / speed;
+
+        // Solve from where the ray nears the bounding sphere, so the quartic's
+        // coefficients stay small for distant rays. `s` measures unit distance from there.
+        let extent = self.major_radius + self.minor_radius;
+        let start = (-o.dot(&d) - extent).max(ray_t.min * speed);
+        let o = o + start * d;
+
+        let (big, small) = (self.major_radius, self.minor_radius);
+        let e = o.dot(&o) - big * big - small * small;
+        let f = o.dot(&d);
+        let four_big2 = 4.0 * big * big;
+        let coefficients = [
+            e * e - four_big2 * (small * small - o.z * o.z),
+            4.0 * f * e + 2.0 * four_big2 * o.z * d.z,
+            2.0 * e + 4.0 * f * f + four_big2 * d.z * d.z,
+            4.0 * f,
+        ];
+
+        let s = solve_quartic(coefficients)
+            .into_iter()
+            .flatten()
+            .filter(|&s| ray_t.surrounds((start + s) / speed))
+            .min_by(|a, b| a.total_cmp(b))?;
+
+        let p = o + s * d;
+        let ring = (p.x * p.x + p.y * p.y).sqrt().max(1e-12);
+        let (cos_phi, sin_phi) = (p.x / ring, p.y / ring);
+        let center = big * Vec3::new(cos_phi, sin_phi, 0.0);
+        let normal = (p - center) / small;
+        let (sin_theta, cos_theta) = (normal.z, ring - big);
+        let cos_theta = cos_theta / small;
+
+        let dpdu = 2.0 * PI * Vec3::new(-p.y, p.x, 0.0);
+        let dpdv =
+            2.0 * PI * small * Vec3::new(-sin_theta * cos_phi, -sin_theta * sin_phi, cos_theta);
+        let hit = LocalHit {
+            t: (start + s) / speed,
+            p,
+            normal,
+            u: turn_fraction(p.x, p.y),
+            v: turn_fraction(cos_theta, sin_theta),
+            dpdu,
+            dpdv,
+            dndu: 2.0 * PI * Vec3::new(-normal.y, normal.x, 0.0),
+            dndv: dpdv / small,
+        };
+        Some(self.frame.record(r, hit, &self.mat))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+unsafe impl Send for Torus {}
+unsafe impl Sync for Torus {}
+
+/// Real roots of the monic quartic `t^4 + c[3] t^3 + c[2] t^2 + c[1] t + c[0]` by
+/// Ferrari's method, each polished with Newton steps.
+fn solve_quartic(c: [f64; 4]) -> [Option<f64>; 4] {
+    let [c0, c1, c2, c3] = c;
+    // Depressed quartic y^4 + p y^2 + q y + r with t = y - c3 / 4.
+    let shift = c3 / 4.0;
+    let p = c2 - 6.0 * shift * shift;
+    let q = c1 - 2.0 * c2 * shift + 8.0 * shift * shift * shift;
+    let r = c0 - c1 * shift + c2 * shift * shift - 3.0 * shift.powi(4);
+
+    let mut roots = [None; 4];
+    let mut push_quadratic = |b: f64, c: f64, slot: usize| {
+        let discriminant = b * b - 4.0 * c;
+        if discriminant >= 0.0 {
+            let sqrtd = discriminant.sqrt();
+            roots[slot] = Some((-b - sqrtd) / 2.0);
+            roots[slot + 1] = Some((-b + sqrtd) / 2.0);
+        }
+    };
+
+    if q.abs() < 1e-12 {
+        // Biquadratic: y^2 = z with z^2 + p z + r = 0.
+        let discriminant = p * p - 4.0 * r;
+        if discriminant >= 0.0 {
+            let sqrtd = discriminant.sqrt();
+            for (slot, z) in [(-p - sqrtd) / 2.0, (-p + sqrtd) / 2.0]
+                .into_iter()
+                .enumerate()
+            {
+                if z >= 0.0 {
+                    roots[2 * slot] = Some(-z.sqrt());
+                    roots[2 * slot + 1] = Some(z.sqrt());
+                }
+            }
+        }
+    } else {
+        // Split into two quadratics through a positive root m of the resolvent cubic
+        // 8 m^3 + 8 p m^2 + (2 p^2 - 8 r) m - q^2 = 0.
+        let m = largest_cubic_root(p, p * p / 4.0 - r, -q * q / 8.0);
+        if m <= 0.0 {
+            return roots;
+        }
+        let w = (2.0 * m).sqrt();
+        push_quadratic(w, p / 2.0 + m - q / (2.0 * w), 0);
+        push_quadratic(-w, p / 2.0 + m + q / (2.0 * w), 2);
+    }
+
+    roots.map(|root| {
+        root.map(|y| {
+            let mut t = y - shift;
+            for _ in 0..2 {
+                let f = (((t + c3) * t + c2) * t + c1) * t + c0;
+                let df = ((4.0 * t + 3.0 * c3) * t + 2.0 * c2) * t + c1;
+                if df != 0.0 {
+                    t -= f / df;
+                }
+            }
+            t
+        })
+    })
+}
+
+/// Largest real root of the monic cubic `m^3 + a m^2 + b m + c`.
+fn largest_cubic_root(a: f64, b: f64, c: f64) -> f64 {
+    let shift = a / 3.0;
+    let p = b - a * shift;
+    let q = 2.0 * shift.powi(3) - b * shift + c;
+    let discriminant = q * q / 4.0 + p.powi(3) / 27.0;
+
+    let y = if discriminant > 0.0 {
+        let sqrtd = discriminant.sqrt();
+        (-q / 2.0 + sqrtd).cbrt() + (-q / 2.0 - sqrtd).cbrt()
+    } else {
+        // Three real roots; the k = 0 branch of the trigonometric form is the largest.
+        let rho = (-p / 3.0).sqrt();
+        let cos_angle = if rho > 0.0 {
+            (-q / (2.0 * rho.powi(3))).clamp(-1.0, 1.0)
+        } else {
+            1.0
+        };
+        2.0 * rho * (cos_angle.acos() / 3.0).cos()
+    };
+    y - shift
+}
+
 #[inline]
 pub fn build_box(a: Point3, b: Point3, mat: Arc<Material>) -> Arc<HittableList> {
     let mut sides = HittableList::default();
@@ -351,6 +1168,213 @@ pub fn build_box(a: Point3, b: Point3, mat: Arc<Material>) -> Arc<HittableList>
     Arc::new(sides)
 }
 
+/// Tessellates the quad `q`, `u`, `v` into a grid of triangles displaced along its normal
+/// by `scale` times the luminance of `height`, looked up at the quad's own coordinates
+/// and undisplaced points. `rate` sets the grid cells per unit length along each edge,
+/// up to `MAX_DISPLACEMENT_CELLS`. The triangles get smooth normals and sit in their own
+/// BVH.
+pub fn build_displaced_quad(
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    mat: Arc<Material>,
+    height: Arc<dyn Texture>,
+    scale: f64,
+    rate: f64,
+) -> Arc<BVHNode> {
+    let columns = displacement_cells(u.length(), rate);
+    let rows = displacement_cells(v.length(), rate);
+    let normal = u.cross(&v).unit_vector();
+
+    // Heights are filtered over one grid cell so the grid does not alias the texture.
+    let footprint = Footprint {
+        dpdx: u / columns as f64,
+        dpdy: v / rows as f64,
+        dudx: 1.0 / columns as f64,
+        dvdy: 1.0 / rows as f64,
+        normal,
+        ..Footprint::default()
+    };
+    let uv = |i: usize, j: usize| (i as f64 / columns as f64, j as f64 / rows as f64);
+    let points: Vec<Point3> = (0..=rows)
+        .flat_map(|j| (0..=columns).map(move |i| (i, j)))
+        .map(|(i, j)| {
+            let (s, t) = uv(i, j);
+            let base = q + s * u + t * v;
+            let h = scale * luminance(&height.filtered_value(s, t, &base, &footprint));
+            base + h * normal
+        })
+        .collect();
+    let point = |i: usize, j: usize| points[j * (columns + 1) + i];
+
+    // Smooth normals from central differences, one-sided along the edges.
+    let normals: Vec<Vec3> = (0..=rows)
+        .flat_map(|j| (0..=columns).map(move |i| (i, j)))
+        .map(|(i, j)| {
+            let du = point((i + 1).min(columns), j) - point(i.saturating_sub(1), j);
+            let dv = point(i, (j + 1).min(rows)) - point(i, j.saturating_sub(1));
+            du.cross(&dv).unit_vector()
+        })
+        .collect();
+    let vertex = |i: usize, j: usize| {
+        let k = j * (columns + 1) + i;
+        (points[k], normals[k], uv(i, j))
+    };
+
+    let mut triangles = HittableList::default();
+    for j in 0..rows {
+        for i in 0..columns {
+            for corners in [
+                [(i, j), (i + 1, j), (i + 1, j + 1)],
+                [(i, j), (i + 1, j + 1), (i, j + 1)],
+            ] {
+                let [a, b, c] = corners.map(|(i, j)| vertex(i, j));
+                triangles.add(Arc::new(Triangle::with_attributes(
+                    [a.0, b.0, c.0],
+                    [a.1, b.1, c.1],
+                    [a.2, b.2, c.2],
+                    mat.clone(),
+                )));
+            }
+        }
+    }
+
+    Arc::new(BVHNode::from(triangles))
+}
+
+/// Most grid cells along one edge of a displaced surface.
+pub const MAX_DISPLACEMENT_CELLS: f64 = 1024.0;
+
+/// Grid cells along an edge of `length` at `rate` cells per unit length, at least one and
+/// at most `MAX_DISPLACEMENT_CELLS`.
+fn displacement_cells(length: f64, rate: f64) -> usize {
+    // A NaN count falls through the clamp and casts to zero.
+    ((length * rate).ceil().clamp(1.0, MAX_DISPLACEMENT_CELLS) as usize).max(1)
+}
+
+/// Subdivides each of `triangles` into a grid of smaller triangles displaced along the
+/// interpolated vertex normal by `scale` times the luminance of `height`, looked up at
+/// the interpolated coordinates and undisplaced points. `rate` sets the grid cells per
+/// unit length along each triangle's longest edge, up to `MAX_DISPLACEMENT_CELLS`, and
+/// the small triangles keep their source triangle's material.
+///
+/// Normals are averaged over all the small triangles meeting at a point, so shading
+/// stays smooth across the source edges where neighbours share normals and coordinates.
+/// Neighbours of quite different sizes get different grid densities, which can open
+/// hairline cracks along their shared edge; meshes of even triangles avoid them.
+pub fn build_displaced_mesh(
+    triangles: &[Triangle],
+    height: Arc<dyn Texture>,
+    scale: f64,
+    rate: f64,
+) -> Arc<BVHNode> {
+    assert!(
+        !triangles.is_empty(),
+        "build_displaced_mesh needs at least one triangle"
+    );
+
+    // Each triangle's grid points by barycentric (i, j) / n, row j holding n + 1 - j.
+    let index = |n: usize, i: usize, j: usize| j * (2 * n + 3 - j) / 2 + i;
+    let grids: Vec<(usize, Vec<_>)> = triangles
+        .iter()
+        .map(|tri| {
+            let [p0, p1, p2] = tri.vertices;
+            let [uv0, uv1, uv2] = tri.uvs;
+            let (e1, e2) = (p1 - p0, p2 - p0);
+            let geometric = e1.cross(&e2).unit_vector();
+            let n = displacement_cells(e1.length().max(e2.length()).max((p2 - p1).length()), rate);
+            let cell = 1.0 / n as f64;
+
+            // Heights are filtered over one grid cell so the grid does not alias the texture.
+            let footprint = Footprint {
+                dpdx: cell * e1,
+                dpdy: cell * e2,
+                dudx: cell * (uv1.0 - uv0.0),
+                dvdx: cell * (uv1.1 - uv0.1),
+                dudy: cell * (uv2.0 - uv0.0),
+                dvdy: cell * (uv2.1 - uv0.1),
+                normal: geometric,
+                ..Footprint::default()
+            };
+            let points = (0..=n)
+                .flat_map(|j| (0..=n - j).map(move |i| (i, j)))
+                .map(|(i, j)| {
+                    let (b1, b2) = (i as f64 * cell, j as f64 * cell);
+                    let b0 = 1.0 - b1 - b2;
+                    let base = b0 * p0 + b1 * p1 + b2 * p2;
+                    let normal =
+                        match b0 * tri.normals[0] + b1 * tri.normals[1] + b2 * tri.normals[2] {
+                            n if n.near_zero() => geometric,
+                            n => n.unit_vector(),
+                        };
+                    let uv = (
+                        b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+                        b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+                    );
+                    let fp = Footprint {
+                        normal,
+                        ..footprint
+                    };
+                    let h = scale * luminance(&height.filtered_value(uv.0, uv.1, &base, &fp));
+                    (base + h * normal, uv)
+                })
+                .collect();
+            (n, points)
+        })
+        .collect();
+
+    let cells = |n: usize| {
+        (0..n).flat_map(move |j| {
+            (0..n - j).flat_map(move |i| {
+                let up = [(i, j), (i + 1, j), (i, j + 1)];
+                let down = [(i + 1, j), (i + 1, j + 1), (i, j + 1)];
+                std::iter::once(up).chain((i + j + 1 < n).then_some(down))
+            })
+        })
+    };
+
+    // Points shared between grids are matched up to a small fraction of the mesh's size.
+    let extent = grids
+        .iter()
+        .flat_map(|(_, points)| points.iter())
+        .fold(0.0_f64, |m, (p, _)| {
+            m.max(p.x.abs()).max(p.y.abs()).max(p.z.abs())
+        });
+    let quantum = 1e-9 * extent.max(1e-12);
+    let key = |p: &Point3| [p.x, p.y, p.z].map(|x| (x / quantum).round() as i64);
+    let mut normals: HashMap<[i64; 3], Vec3> = HashMap::new();
+    for (n, points) in &grids {
+        for corners in cells(*n) {
+            let [a, b, c] = corners.map(|(i, j)| points[index(*n, i, j)].0);
+            // Unnormalized, so larger triangles weigh more.
+            let face = (b - a).cross(&(c - a));
+            for p in [a, b, c] {
+                *normals.entry(key(&p)).or_default() += face;
+            }
+        }
+    }
+
+    let mut displaced = HittableList::default();
+    for (tri, (n, points)) in triangles.iter().zip(&grids) {
+        for corners in cells(*n) {
+            let [a, b, c] = corners.map(|(i, j)| points[index(*n, i, j)]);
+            let geometric = (b.0 - a.0).cross(&(c.0 - a.0));
+            let normal = |p: &Point3| match normals[&key(p)] {
+                n if n.near_zero() => geometric.unit_vector(),
+                n => n.unit_vector(),
+            };
+            displaced.add(Arc::new(Triangle::with_attributes(
+                [a.0, b.0, c.0],
+                [normal(&a.0), normal(&b.0), normal(&c.0)],
+                [a.1, b.1, c.1],
+                tri.mat.clone(),
+            )));
+        }
+    }
+
+    Arc::new(BVHNode::from(displaced))
+}
+
 #[derive(Debug, Clone)]
 pub struct Translate {
     object: Arc<dyn Hittable>,
@@ -375,6 +1399,7 @@ impl Hittable for Translate {
         if let Some(rec) = self.object.hit(&offset_r, ray_t) {
             return Some(HitRecord {
                 p: rec.p + self.offset,
+                object: Some(rec.object.unwrap_or(ObjectSpace::new(rec.p, rec.normal))),
                 ..rec
             });
         }
@@ -382,6 +1407,11 @@ impl Hittable for Translate {
         None
     }
 
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        let offset_r = Ray::new(r.origin() - self.offset, r.direction(), r.time());
+        self.object.transmittance(&offset_r, ray_t)
+    }
+
     fn bounding_box(&self) -> AABB {
         self.bbox
     }
@@ -390,6 +1420,178 @@ impl Hittable for Translate {
 unsafe impl Send for Translate {}
 unsafe impl Sync for Translate {}
 
+/// Fine surface detail that tilts the shading normal without changing the geometry.
+#[derive(Clone, Debug)]
+pub enum SurfaceDetail {
+    /// Heights from the luminance of a scalar texture, displacing the surface up to
+    /// `scale` units along its outward normal.
+    Bump {
+        height: Arc<dyn Texture>,
+        scale: f64,
+    },
+    /// A tangent-space normal map: red, green and blue in [0, 1] encode the normal along
+    /// `dpdu`, `dpdv` and the outward normal. `strength` scales the tilt.
+    NormalMap {
+        map: Arc<dyn Texture>,
+        strength: f64,
+    },
+}
+
+/// Applies a `SurfaceDetail` to the shading normal of every hit on `object`. Objects
+/// need texture partials (`dpdu`, `dpdv`); hits without them are left unchanged.
+#[derive(Clone, Debug)]
+pub struct Bumped {
+    object: Arc<dyn Hittable>,
+    detail: SurfaceDetail,
+}
+
+impl Bumped {
+    /// Texture coordinate step for bump differences when the hit has no footprint.
+    const DEFAULT_DELTA: f64 = 0.0005;
+
+    pub fn new(object: Arc<dyn Hittable>, detail: SurfaceDetail) -> Self {
+        Self { object, detail }
+    }
+
+    /// Perturbed unit normal on the outward side, following pbrt's bump mapping.
+    fn bump(&self, rec: &HitRecord, height: &dyn Texture, scale: f64, outward: Vec3) -> Vec3 {
+        let sign = if rec.front_face { 1.0 } else { -1.0 };
+        let fp = &rec.footprint;
+        let delta = |d: f64| if d > 0.0 { d } else { Self::DEFAULT_DELTA };
+        let du = delta(0.5 * (fp.dudx.abs() + fp.dudy.abs()));
+        let dv = delta(0.5 * (fp.dvdx.abs() + fp.dvdy.abs()));
+
+        let displace =
+            |u: f64, v: f64, p: Point3| scale * luminance(&height.filtered_value(u, v, &p, fp));
+        let d = displace(rec.u, rec.v, rec.p);
+        let d_du = (displace(rec.u + du, rec.v, rec.p + du * rec.dpdu) - d) / du;
+        let d_dv = (displace(rec.u, rec.v + dv, rec.p + dv * rec.dpdv) - d) / dv;
+
+        let dpdu = rec.dpdu + d_du * outward + d * sign * rec.dndu;
+        let dpdv = rec.dpdv + d_dv * outward + d * sign * rec.dndv;
+        let n = dpdu.cross(&dpdv).unit_vector();
+        if n.dot(&outward) < 0.0 {
+            -n
+        } else {
+            n
+        }
+    }
+
+    fn normal_map(rec: &HitRecord, map: &dyn Texture, strength: f64, outward: Vec3) -> Vec3 {
+        let c = map.filtered_value(rec.u, rec.v, &rec.p, &rec.footprint);
+        let tangent = (rec.dpdu - rec.dpdu.dot(&outward) * outward).unit_vector();
+        let bitangent = match outward.cross(&tangent) {
+            b if b.dot(&rec.dpdv) < 0.0 => -b,
+            b => b,
+        };
+        let local = Vec3::new(
+            strength * (2.0 * c.x - 1.0),
+            strength * (2.0 * c.y - 1.0),
+            (2.0 * c.z - 1.0).max(0.0),
+        );
+        let n = local.x * tangent + local.y * bitangent + local.z * outward;
+        if n.near_zero() {
+            outward
+        } else {
+            n.unit_vector()
+        }
+    }
+}
+
+impl Hittable for Bumped {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let mut rec = self.object.hit(r, ray_t)?;
+        if rec.dpdu.near_zero() || rec.dpdv.near_zero() {
+            return Some(rec);
+        }
+
+        // The camera fills in the footprint after the hit; the detail needs it now to
+        // filter its texture.
+        rec.set_footprint(r);
+        let outward = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+        let n = match &self.detail {
+            SurfaceDetail::Bump { height, scale } => {
+                self.bump(&rec, height.as_ref(), *scale, outward)
+            }
+            SurfaceDetail::NormalMap { map, strength } => {
+                Self::normal_map(&rec, map.as_ref(), *strength, outward)
+            }
+        };
+        rec.normal = if rec.front_face { n } else { -n };
+        Some(rec)
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        self.object.transmittance(r, ray_t)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.object.bounding_box()
+    }
+}
+
+unsafe impl Send for Bumped {}
+unsafe impl Sync for Bumped {}
+
+/// How an `AlphaMasked` object turns mask values into hits and misses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlphaMode {
+    /// Hits where the mask is at least the threshold; crisp leaf and fence edges.
+    Cutout(f64),
+    /// Hits with probability equal to the mask, so partial alpha averages out to
+    /// partial coverage over many samples.
+    Stochastic,
+}
+
+/// Lets rays pass through `object` where the luminance of `mask` says it is transparent,
+/// carrying on to whatever lies behind, the object's own far side included. Shadow rays
+/// pass through the same holes.
+#[derive(Clone, Debug)]
+pub struct AlphaMasked {
+    object: Arc<dyn Hittable>,
+    mask: Arc<dyn Texture>,
+    mode: AlphaMode,
+}
+
+impl AlphaMasked {
+    pub fn new(object: Arc<dyn Hittable>, mask: Arc<dyn Texture>, mode: AlphaMode) -> Self {
+        Self { object, mask, mode }
+    }
+
+    fn opaque(&self, rec: &HitRecord) -> bool {
+        let alpha = luminance(&self.mask.value(rec.u, rec.v, &rec.p));
+        match self.mode {
+            AlphaMode::Cutout(threshold) => alpha >= threshold,
+            AlphaMode::Stochastic => alpha >= 1.0 || random_double() < alpha,
+        }
+    }
+}
+
+impl Hittable for AlphaMasked {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let mut t_min = ray_t.min;
+        loop {
+            let rec = self.object.hit(r, &mut Interval::new(t_min, ray_t.max))?;
+            if self.opaque(&rec) {
+                return Some(rec);
+            }
+            // Just past the rejected hit, since some shapes accept hits at `min` itself.
+            t_min = rec.t + 1e-9 * rec.t.abs().max(1.0);
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.object.bounding_box()
+    }
+}
+
+unsafe impl Send for AlphaMasked {}
+unsafe impl Sync for AlphaMasked {}
+
 #[derive(Debug, Clone)]
 pub struct RotateY {
     object: Arc<dyn Hittable>,
@@ -437,8 +1639,8 @@ impl RotateY {
     }
 }
 
-impl Hittable for RotateY {
-    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+impl RotateY {
+    fn rotate_ray(&self, r: &Ray) -> Ray {
         let mut origin = r.origin();
         let mut direction = r.direction();
 
@@ -448,23 +1650,56 @@ impl Hittable for RotateY {
         direction[0] = self.cos_theta * r.direction()[0] - self.sin_theta * r.direction()[2];
         direction[2] = self.sin_theta * r.direction()[0] + self.cos_theta * r.direction()[2];
 
-        let rotated_r = Ray::new(origin, direction, r.time());
+        Ray::new(origin, direction, r.time())
+    }
 
-        if let Some(rec) = self.object.hit(&rotated_r, ray_t) {
-            let mut p = rec.p;
-            p[0] = self.cos_theta * rec.p[0] + self.sin_theta * rec.p[2];
-            p[2] = -self.sin_theta * rec.p[0] + self.cos_theta * rec.p[2];
+    /// Rotates a direction from world space into object space, as `rotate_ray` does.
+    fn to_object(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x - self.sin_theta * v.z,
+            v.y,
+            self.sin_theta * v.x + self.cos_theta * v.z,
+        )
+    }
 
-            let mut normal = rec.normal;
-            normal[0] = self.cos_theta * rec.normal[0] + self.sin_theta * rec.normal[2];
-            normal[2] = -self.sin_theta * rec.normal[0] + self.cos_theta * rec.normal[2];
+    /// Rotates a direction from object space back into world space.
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        Vec3::new(
+            self.cos_theta * v.x + self.sin_theta * v.z,
+            v.y,
+            -self.sin_theta * v.x + self.cos_theta * v.z,
+        )
+    }
+}
 
-            return Some(HitRecord { p, normal, ..rec });
+impl Hittable for RotateY {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let rotated_r = self.rotate_ray(r);
+
+        if let Some(rec) = self.object.hit(&rotated_r, ray_t) {
+            return Some(HitRecord {
+                p: self.to_world(rec.p),
+                normal: self.to_world(rec.normal),
+                dpdu: self.to_world(rec.dpdu),
+                dpdv: self.to_world(rec.dpdv),
+                dndu: self.to_world(rec.dndu),
+                dndv: self.to_world(rec.dndv),
+                object: Some(
+                    rec.object
+                        .unwrap_or(ObjectSpace::new(rec.p, rec.normal))
+                        .through(|v| self.to_object(v)),
+                ),
+                ..rec
+            });
         }
 
         None
     }
 
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        self.object.transmittance(&self.rotate_ray(r), ray_t)
+    }
+
     fn bounding_box(&self) -> AABB {
         self.bbox
     }
@@ -477,68 +1712,255 @@ unsafe impl Sync for RotateY {}
 pub struct ConstantMedium {
     boundary: Arc<dyn Hittable>,
     neg_inv_density: f64,
+    extinction: Color,
     phase_function: Arc<Material>,
 }
 
 impl ConstantMedium {
     pub fn new(boundary: Arc<dyn Hittable>, density: f64, tex: Arc<dyn Texture>) -> Self {
+        Self::with_phase(boundary, density, tex, PhaseFunction::Isotropic)
+    }
+
+    pub fn with_phase(
+        boundary: Arc<dyn Hittable>,
+        density: f64,
+        tex: Arc<dyn Texture>,
+        phase: PhaseFunction,
+    ) -> Self {
         Self {
             boundary,
             neg_inv_density: -1.0 / density,
-            phase_function: Arc::new(Material::Isotropic { tex }),
+            extinction: Color::new(density, density, density),
+            phase_function: Arc::new(Material::Volume { tex, phase }),
+        }
+    }
+
+    /// Medium with separate, possibly colored, absorption and scattering and optional
+    /// emission. Collisions are sampled against the largest extinction channel.
+    pub fn with_coefficients(
+        boundary: Arc<dyn Hittable>,
+        coefficients: MediumCoefficients,
+    ) -> Self {
+        let extinction = coefficients.extinction();
+        let majorant = extinction.max_component();
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / majorant,
+            extinction,
+            phase_function: Arc::new(Material::Medium {
+                coefficients,
+                density: None,
+                majorant,
+            }),
         }
     }
 }
 
+/// The part of `ray_t` that `r` spends inside a closed `boundary`, along with the record
+/// of where it leaves.
+fn boundary_span(
+    boundary: &dyn Hittable,
+    r: &Ray,
+    ray_t: &Interval,
+) -> Option<(f64, f64, HitRecord)> {
+    let rec1 = boundary.hit(r, &mut UNIVERSE.clone())?;
+    let rec2 = boundary.hit(r, &mut Interval::new(rec1.t + 0.0001, f64::INFINITY))?;
+
+    let t0 = rec1.t.max(ray_t.min);
+    let t1 = rec2.t.min(ray_t.max);
+    if t0 >= t1 {
+        return None;
+    }
+
+    Some((t0.max(0.0), t1, rec2))
+}
+
 impl Hittable for ConstantMedium {
     fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
-        if let Some(mut rec1) = self.boundary.hit(r, &mut UNIVERSE) {
-            if let Some(mut rec2) = self
-                .boundary
-                .hit(r, &mut Interval::new(rec1.t + 0.0001, f64::INFINITY))
-            {
-                if rec1.t < ray_t.min {
-                    rec1.t = ray_t.min;
-                }
-                if rec2.t > ray_t.max {
-                    rec2.t = ray_t.max;
-                }
+        let (t0, t1, rec2) = boundary_span(self.boundary.as_ref(), r, ray_t)?;
 
-                if rec1.t >= rec2.t {
-                    return None;
-                }
+        let ray_length = r.direction().length();
+        let distance_inside_boundary = (t1 - t0) * ray_length;
+        let hit_distance = self.neg_inv_density * random_double().ln();
 
-                if rec1.t < 0.0 {
-                    rec1.t = 0.0;
-                }
+        if hit_distance > distance_inside_boundary {
+            return None;
+        }
 
-                let ray_length = r.direction().length();
-                let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
-                let hit_distance = self.neg_inv_density * random_double().ln();
+        let t = t0 + hit_distance / ray_length;
+        let p = r.at(t);
+        let normal = Vec3::new(1.0, 0.0, 0.0);
+        let front_face = true;
+        let mat = self.phase_function.clone();
 
-                if hit_distance > distance_inside_boundary {
-                    return None;
-                }
+        Some(HitRecord {
+            p,
+            normal,
+            mat,
+            t,
+            u: rec2.u,
+            v: rec2.v,
+            front_face,
+            dpdu: Vec3::default(),
+            dpdv: Vec3::default(),
+            dndu: Vec3::default(),
+            dndv: Vec3::default(),
+            footprint: Footprint::default(),
+            object: None,
+        })
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        match boundary_span(self.boundary.as_ref(), r, &ray_t) {
+            Some((t0, t1, _)) => {
+                let distance = (t1 - t0) * r.direction().length();
+                Color::new(
+                    (-self.extinction.x * distance).exp(),
+                    (-self.extinction.y * distance).exp(),
+                    (-self.extinction.z * distance).exp(),
+                )
+            }
+            None => Color::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.boundary.bounding_box()
+    }
+}
+
+unsafe impl Send for ConstantMedium {}
+unsafe impl Sync for ConstantMedium {}
+
+/// Participating medium whose density varies through space, sampled by delta tracking
+/// against the field's maximum density.
+#[derive(Debug, Clone)]
+pub struct HeterogeneousMedium {
+    boundary: Arc<dyn Hittable>,
+    density: Arc<dyn DensityField>,
+    extinction: Color,
+    majorant: f64,
+    phase_function: Arc<Material>,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(
+        boundary: Arc<dyn Hittable>,
+        density: Arc<dyn DensityField>,
+        tex: Arc<dyn Texture>,
+    ) -> Self {
+        Self::with_phase(boundary, density, tex, PhaseFunction::Isotropic)
+    }
+
+    pub fn with_phase(
+        boundary: Arc<dyn Hittable>,
+        density: Arc<dyn DensityField>,
+        tex: Arc<dyn Texture>,
+        phase: PhaseFunction,
+    ) -> Self {
+        Self {
+            boundary,
+            majorant: density.max_density(),
+            density,
+            extinction: Color::new(1.0, 1.0, 1.0),
+            phase_function: Arc::new(Material::Volume { tex, phase }),
+        }
+    }
 
-                let t = rec1.t + hit_distance / ray_length;
-                let p = r.at(t);
-                let normal = Vec3::new(1.0, 0.0, 0.0);
-                let front_face = true;
-                let mat = self.phase_function.clone();
+    /// Medium whose coefficients are `coefficients` scaled by the local density.
+    pub fn with_coefficients(
+        boundary: Arc<dyn Hittable>,
+        density: Arc<dyn DensityField>,
+        coefficients: MediumCoefficients,
+    ) -> Self {
+        let extinction = coefficients.extinction();
+        let majorant = density.max_density() * extinction.max_component();
+        Self {
+            boundary,
+            density: density.clone(),
+            extinction,
+            majorant,
+            phase_function: Arc::new(Material::Medium {
+                coefficients,
+                density: Some(density),
+                majorant,
+            }),
+        }
+    }
+
+    /// Exponential free-flight step through the majorant medium, in ray parameter units.
+    #[inline]
+    fn step(&self, ray_length: f64) -> f64 {
+        -(1.0 - random_double()).ln() / (self.majorant * ray_length)
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        if self.majorant <= 0.0 {
+            return None;
+        }
+        let (t0, t1, rec2) = boundary_span(self.boundary.as_ref(), r, ray_t)?;
+
+        // Colored media resolve null collisions in the material, where the weights are.
+        let resolves_nulls = matches!(*self.phase_function, Material::Medium { .. });
+
+        let ray_length = r.direction().length();
+        let mut t = t0;
+        loop {
+            t += self.step(ray_length);
+            if t >= t1 {
+                return None;
+            }
 
+            let p = r.at(t);
+            // Real collision with probability density / majorant, otherwise a null one.
+            if resolves_nulls
+                || random_double() * self.majorant < self.density.density(&p) * self.extinction.x
+            {
                 return Some(HitRecord {
                     p,
-                    normal,
-                    mat,
+                    normal: Vec3::new(1.0, 0.0, 0.0),
+                    mat: self.phase_function.clone(),
                     t,
                     u: rec2.u,
                     v: rec2.v,
-                    front_face,
+                    front_face: true,
+                    dpdu: Vec3::default(),
+                    dpdv: Vec3::default(),
+                    dndu: Vec3::default(),
+                    dndv: Vec3::default(),
+                    footprint: Footprint::default(),
+                    object: None,
                 });
             }
         }
+    }
 
-        None
+    /// Ratio tracking estimate of the transmittance.
+    fn transmittance(&self, r: &Ray, ray_t: Interval) -> Color {
+        let unoccluded = Color::new(1.0, 1.0, 1.0);
+        if self.majorant <= 0.0 {
+            return unoccluded;
+        }
+        let Some((t0, t1, _)) = boundary_span(self.boundary.as_ref(), r, &ray_t) else {
+            return unoccluded;
+        };
+
+        let ray_length = r.direction().length();
+        let mut tr = unoccluded;
+        let mut t = t0;
+        loop {
+            t += self.step(ray_length);
+            if t >= t1 {
+                return tr;
+            }
+            let sigma_t = self.density.density(&r.at(t)) * self.extinction;
+            tr = tr * (unoccluded - sigma_t / self.majorant);
+            if tr.max_component() <= 0.0 {
+                return Color::default();
+            }
+        }
     }
 
     fn bounding_box(&self) -> AABB {
@@ -546,5 +1968,5 @@ impl Hittable for ConstantMedium {
     }
 }
 
-unsafe impl Send for ConstantMedium {}
-unsafe impl Sync for ConstantMedium {}
+unsafe impl Send for HeterogeneousMedium {}
+unsafe impl Sync for HeterogeneousMedium {}
@@ -1,10 +1,22 @@
 use super::vec3::*;
 
+/// Rays offset by one pixel in x and y that travel alongside a camera ray, so the
+/// footprint of the pixel on the surfaces it reaches can be estimated.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RayDifferentials {
+    pub rx_origin: Point3,
+    pub rx_direction: Vec3,
+    pub ry_origin: Point3,
+    pub ry_direction: Vec3,
+}
+
 #[derive(Clone, Copy, Default)]
 pub struct Ray {
     orig: Point3,
     dir: Vec3,
     tm: f64,
+    wavelength: Option<f64>,
+    differentials: Option<RayDifferentials>,
 }
 
 impl Ray {
@@ -13,9 +25,16 @@ impl Ray {
             orig: origin,
             dir: direction,
             tm: t,
+            wavelength: None,
+            differentials: None,
         }
     }
 
+    /// The same ray carrying a single `wavelength` in nanometres, for spectral rendering.
+    pub fn with_wavelength(self, wavelength: Option<f64>) -> Self {
+        Self { wavelength, ..self }
+    }
+
     pub fn origin(&self) -> Point3 {
         self.orig
     }
@@ -28,6 +47,21 @@ impl Ray {
         self.tm
     }
 
+    pub fn wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
+
+    pub fn with_differentials(self, differentials: Option<RayDifferentials>) -> Self {
+        Self {
+            differentials,
+            ..self
+        }
+    }
+
+    pub fn differentials(&self) -> Option<RayDifferentials> {
+        self.differentials
+    }
+
     pub fn at(&self, t: f64) -> Point3 {
         self.orig + t * self.dir
     }
@@ -0,0 +1,321 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    aabb::AABB,
+    interval::Interval,
+    material::Material,
+    primitive::{HitRecord, Hittable},
+    ray::Ray,
+    texture::Footprint,
+    utils::degrees_to_radians,
+    vec3::{Point3, Vec3},
+};
+
+/// Signed distance function: negative inside the shape, positive outside, and never more
+/// than the distance to the surface, so sphere tracing can step by it safely. Primitives
+/// are centered on the origin; the operators combine and reshape other fields.
+#[derive(Clone, Debug)]
+pub enum Sdf {
+    Sphere {
+        radius: f64,
+    },
+    /// Axis-aligned box with the given half extents.
+    Box {
+        half_extents: Vec3,
+    },
+    /// Box with its edges rounded off by `radius`, within the same half extents.
+    RoundBox {
+        half_extents: Vec3,
+        radius: f64,
+    },
+    /// Torus around the y axis.
+    Torus {
+        major_radius: f64,
+        minor_radius: f64,
+    },
+    /// Cylinder around the y axis from `-half_height` to `half_height`.
+    Cylinder {
+        radius: f64,
+        half_height: f64,
+    },
+    /// Segment from `a` to `b` thickened by `radius`.
+    Capsule {
+        a: Point3,
+        b: Point3,
+        radius: f64,
+    },
+    /// Distance estimate of the Mandelbulb fractal of the given power, reaching out to a
+    /// radius of about 1.2 for power 8.
+    Mandelbulb {
+        power: f64,
+        iterations: u32,
+    },
+
+    Union(Arc<Sdf>, Arc<Sdf>),
+    Intersection(Arc<Sdf>, Arc<Sdf>),
+    /// The first field with the second cut away.
+    Subtraction(Arc<Sdf>, Arc<Sdf>),
+    /// Union blending the two surfaces over a distance of about `k`.
+    SmoothUnion(Arc<Sdf>, Arc<Sdf>, f64),
+    /// Subtraction with the cut rounded over a distance of about `k`.
+    SmoothSubtraction(Arc<Sdf>, Arc<Sdf>, f64),
+
+    Translate(Arc<Sdf>, Vec3),
+    /// Uniform scale about the origin.
+    Scale(Arc<Sdf>, f64),
+    /// Infinite copies every `period` along each axis with a positive period component;
+    /// the copy at the origin should fit within one period.
+    Repeat(Arc<Sdf>, Vec3),
+    /// Turns each horizontal slice about the y axis by `degrees` per unit of height. This
+    /// stretches distances, so the marcher needs a smaller step scale.
+    Twist(Arc<Sdf>, f64),
+}
+
+impl Sdf {
+    pub fn distance(&self, p: Point3) -> f64 {
+        match self {
+            Self::Sphere { radius } => p.length() - radius,
+            Self::Box { half_extents } => box_distance(p, *half_extents),
+            Self::RoundBox {
+                half_extents,
+                radius,
+            } => {
+                let inner = *half_extents - Vec3::new(*radius, *radius, *radius);
+                box_distance(p, inner) - radius
+            }
+            Self::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let ring = (p.x * p.x + p.z * p.z).sqrt() - major_radius;
+                (ring * ring + p.y * p.y).sqrt() - minor_radius
+            }
+            Self::Cylinder {
+                radius,
+                half_height,
+            } => {
+                let radial = (p.x * p.x + p.z * p.z).sqrt() - radius;
+                let axial = p.y.abs() - half_height;
+                radial.max(axial).min(0.0) + radial.max(0.0).hypot(axial.max(0.0))
+            }
+            Self::Capsule { a, b, radius } => {
+                let (pa, ba) = (p - *a, *b - *a);
+                let h = (pa.dot(&ba) / ba.dot(&ba).max(1e-12)).clamp(0.0, 1.0);
+                (pa - h * ba).length() - radius
+            }
+            Self::Mandelbulb { power, iterations } => mandelbulb_distance(p, *power, *iterations),
+
+            Self::Union(a, b) => a.distance(p).min(b.distance(p)),
+            Self::Intersection(a, b) => a.distance(p).max(b.distance(p)),
+            Self::Subtraction(a, b) => a.distance(p).max(-b.distance(p)),
+            Self::SmoothUnion(a, b, k) => {
+                let (d1, d2) = (a.distance(p), b.distance(p));
+                let h = (0.5 + 0.5 * (d2 - d1) / k).clamp(0.0, 1.0);
+                d2 + (d1 - d2) * h - k * h * (1.0 - h)
+            }
+            Self::SmoothSubtraction(a, b, k) => {
+                let (d1, d2) = (a.distance(p), b.distance(p));
+                let h = (0.5 - 0.5 * (d1 + d2) / k).clamp(0.0, 1.0);
+                d1 + (-d2 - d1) * h + k * h * (1.0 - h)
+            }
+
+            Self::Translate(field, offset) => field.distance(p - *offset),
+            Self::Scale(field, factor) => field.distance(p / *factor) * factor,
+            Self::Repeat(field, period) => {
+                let wrap = |x: f64, period: f64| {
+                    if period > 0.0 {
+                        x - period * (x / period).round()
+                    } else {
+                        x
+                    }
+                };
+                field.distance(Point3::new(
+                    wrap(p.x, period.x),
+                    wrap(p.y, period.y),
+                    wrap(p.z, period.z),
+                ))
+            }
+            Self::Twist(field, degrees) => {
+                let (sin, cos) = degrees_to_radians(degrees * p.y).sin_cos();
+                field.distance(Point3::new(
+                    cos * p.x - sin * p.z,
+                    p.y,
+                    sin * p.x + cos * p.z,
+                ))
+            }
+        }
+    }
+
+    /// Gradient direction by central differences over a tetrahedron of samples.
+    fn normal(&self, p: Point3, h: f64) -> Vec3 {
+        [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ]
+        .iter()
+        .fold(Vec3::default(), |n, &k| n + k * self.distance(p + h * k))
+        .unit_vector()
+    }
+}
+
+fn box_distance(p: Point3, half_extents: Vec3) -> f64 {
+    let q = Vec3::new(
+        p.x.abs() - half_extents.x,
+        p.y.abs() - half_extents.y,
+        p.z.abs() - half_extents.z,
+    );
+    let outside = Vec3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+    outside + q.x.max(q.y).max(q.z).min(0.0)
+}
+
+fn mandelbulb_distance(p: Point3, power: f64, iterations: u32) -> f64 {
+    let mut z = p;
+    let mut dr = 1.0;
+    let mut r = z.length();
+    for _ in 0..iterations {
+        if r > 2.0 {
+            break;
+        }
+        if r < 1e-12 {
+            return -1e-12;
+        }
+        let theta = (z.z / r).acos() * power;
+        let phi = z.y.atan2(z.x) * power;
+        dr = r.powf(power - 1.0) * power * dr + 1.0;
+        z = r.powf(power)
+            * Vec3::new(
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                theta.cos(),
+            )
+            + p;
+        r = z.length();
+    }
+    0.5 * r.ln() * r / dr
+}
+
+/// Surface of a signed distance field, found by sphere tracing: the ray advances by the
+/// distance to the nearest surface until it comes within a small tolerance of it. Only
+/// the part of the ray inside `bounds` is marched, and `bounds` is also what the BVH
+/// sees, so it must enclose the surface.
+///
+/// Normals come from finite differences of the field. Texture coordinates are spherical
+/// in the normal direction, like a sphere's, but follow no parameterization of the
+/// surface: the hit has no `dpdu` or `dpdv`, so image textures go unfiltered and bump
+/// maps do nothing. Projected or solid textures suit these shapes better.
+#[derive(Clone, Debug)]
+pub struct SdfObject {
+    field: Sdf,
+    mat: Arc<Material>,
+    bbox: AABB,
+    /// Hits closer to the surface than this count as on it.
+    tolerance: f64,
+    step_scale: f64,
+    max_steps: u32,
+}
+
+impl SdfObject {
+    pub fn new(field: Sdf, bounds: AABB, mat: Arc<Material>) -> Self {
+        Self {
+            field,
+            mat,
+            bbox: bounds,
+            tolerance: 1e-4,
+            step_scale: 1.0,
+            max_steps: 256,
+        }
+    }
+
+    /// Steps by only this fraction of the distance, for fields that overestimate it such
+    /// as twisted ones, at the cost of more steps.
+    pub fn with_step_scale(mut self, step_scale: f64) -> Self {
+        self.step_scale = step_scale.clamp(0.01, 1.0);
+        self.max_steps = (256.0 / self.step_scale) as u32;
+        self
+    }
+
+    /// Stops closer to or further from the surface than the default of 1e-4. Fractals
+    /// have detail at every scale, and a tolerance near the size of a pixel saves most of
+    /// their steps.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance.max(1e-9);
+        self
+    }
+}
+
+impl Hittable for SdfObject {
+    fn hit(&self, r: &Ray, ray_t: &mut Interval) -> Option<HitRecord> {
+        let (t_enter, t_exit) = self.bbox.clip(r)?;
+        let (t_start, t_end) = (t_enter.max(ray_t.min), t_exit.min(ray_t.max));
+        if t_start > t_end {
+            return None;
+        }
+
+        let speed = r.direction().length();
+        let mut t = t_start;
+        let mut steps = 0;
+        let mut distance = self.field.distance(r.at(t));
+        // A ray leaving a surface, such as one refracted into or reflected off it, starts
+        // within the tolerance: step off it before deciding which side it is on. Rays
+        // from outside the bounds start on the boundary, and can only be outside.
+        let leaving = t_enter <= ray_t.min;
+        while leaving && distance.abs() < self.tolerance && t < t_end && steps < self.max_steps {
+            t += 2.0 * self.tolerance / speed;
+            distance = self.field.distance(r.at(t));
+            steps += 1;
+        }
+        let side = if leaving && distance < 0.0 { -1.0 } else { 1.0 };
+
+        loop {
+            if t > t_end || steps >= self.max_steps {
+                return None;
+            }
+            distance = side * self.field.distance(r.at(t));
+            if distance < self.tolerance {
+                break;
+            }
+            t += self.step_scale * distance / speed;
+            steps += 1;
+        }
+        if !ray_t.surrounds(t) {
+            return None;
+        }
+
+        let p = r.at(t);
+        let outward_normal = self.field.normal(p, 0.5 * self.tolerance);
+        let front_face = r.direction().dot(&outward_normal) < 0.0;
+        let normal = if front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+        let theta = (-outward_normal.y).clamp(-1.0, 1.0).acos();
+        let phi = (-outward_normal.z).atan2(outward_normal.x) + PI;
+
+        Some(HitRecord {
+            t,
+            p,
+            normal,
+            mat: self.mat.clone(),
+            front_face,
+            u: phi / (2.0 * PI),
+            v: theta / PI,
+            dpdu: Vec3::default(),
+            dpdv: Vec3::default(),
+            dndu: Vec3::default(),
+            dndv: Vec3::default(),
+            footprint: Footprint::default(),
+            object: None,
+        })
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
+
+unsafe impl Send for SdfObject {}
+unsafe impl Sync for SdfObject {}
@@ -0,0 +1,192 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::{xyz_to_rgb, Color},
+    environment::Environment,
+    light::Light,
+    vec3::Vec3,
+};
+
+/// Angular radius of the sun as seen from the ground, in degrees.
+const SUN_ANGULAR_RADIUS: f64 = 0.2665;
+/// Illuminance of the sun above the atmosphere, in the sky's kcd/m^2 units.
+const SUN_ILLUMINANCE: f64 = 128.0;
+
+/// Perez distribution coefficients A-E for one of the Y, x or y channels.
+#[derive(Clone, Copy, Debug)]
+struct Perez([f64; 5]);
+
+impl Perez {
+    fn eval(&self, cos_theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = self.0;
+        let cos_gamma = gamma.cos();
+        (1.0 + a * (b / cos_theta.max(0.01)).exp())
+            * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+    }
+}
+
+/// Preetham analytic daylight sky, +Y up. Radiance is in kcd/m^2 times `intensity`.
+///
+/// Below the horizon the sky shows a uniform diffuse ground lit by the sky and sun.
+/// The sun disk itself is not part of the sky; add `sun()` to the camera lights.
+#[derive(Clone, Debug)]
+pub struct PreethamSky {
+    sun_direction: Vec3,
+    turbidity: f64,
+    intensity: f64,
+    perez: [Perez; 3],
+    zenith: Vec3,
+    ground: Color,
+}
+
+impl PreethamSky {
+    /// `sun_direction` points towards the sun; `turbidity` ranges from about 2 (clear)
+    /// to 10 (hazy).
+    pub fn new(sun_direction: Vec3, turbidity: f64, ground_albedo: Color, intensity: f64) -> Self {
+        let t = turbidity;
+        let mut sun_direction = sun_direction.unit_vector();
+        // The model is only defined for a sun above the horizon.
+        if sun_direction.y < 0.01 {
+            sun_direction.y = 0.01;
+            sun_direction = sun_direction.unit_vector();
+        }
+        let theta_s = sun_direction.y.acos();
+
+        let perez = [
+            Perez([
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ]),
+            Perez([
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ]),
+            Perez([
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ]),
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_y = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.0);
+        let (th, th2, th3, t2) = (theta_s, theta_s * theta_s, theta_s.powi(3), t * t);
+        let zenith_x = t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let zenith_yc = t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+
+        let mut sky = Self {
+            sun_direction,
+            turbidity,
+            intensity,
+            perez,
+            zenith: Vec3::new(zenith_y, zenith_x, zenith_yc),
+            ground: Color::default(),
+        };
+
+        // Irradiance on the ground from the whole upper hemisphere plus the sun.
+        let (n_theta, n_phi) = (32, 64);
+        let d_theta = 0.5 * PI / n_theta as f64;
+        let d_phi = 2.0 * PI / n_phi as f64;
+        let sky_irradiance: Color = (0..n_theta)
+            .flat_map(|i| (0..n_phi).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                let theta = (i as f64 + 0.5) * d_theta;
+                let phi = (j as f64 + 0.5) * d_phi;
+                let d = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                sky.sky_radiance(&d) * (theta.cos() * theta.sin() * d_theta * d_phi)
+            })
+            .sum();
+        let sun_irradiance = sky.sun_irradiance() * sun_direction.y;
+        sky.ground = ground_albedo * (sky_irradiance + sun_irradiance) / PI;
+
+        sky
+    }
+
+    /// Directional light for the sun disk, matching this sky's sun position and color.
+    pub fn sun(&self) -> Light {
+        Light::Directional {
+            direction: -self.sun_direction,
+            irradiance: self.sun_irradiance(),
+            angular_radius: SUN_ANGULAR_RADIUS,
+        }
+    }
+
+    /// Sun irradiance at normal incidence after Rayleigh and aerosol extinction.
+    fn sun_irradiance(&self) -> Color {
+        let theta_s = self.sun_direction.y.acos();
+        let relative_air_mass =
+            1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_s.to_degrees()).powf(-1.253));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+
+        // Representative wavelengths for the R, G and B channels, in micrometres.
+        let transmittance = |lambda: f64| {
+            let rayleigh = (-0.008735 * lambda.powf(-4.08) * relative_air_mass).exp();
+            let aerosol = (-beta * lambda.powf(-1.3) * relative_air_mass).exp();
+            rayleigh * aerosol
+        };
+
+        self.intensity
+            * SUN_ILLUMINANCE
+            * Color::new(
+                transmittance(0.65),
+                transmittance(0.57),
+                transmittance(0.475),
+            )
+    }
+
+    fn sky_radiance(&self, direction: &Vec3) -> Color {
+        let cos_theta = direction.y;
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let theta_s = self.sun_direction.y.acos();
+
+        let [luminance, x, y] = [0, 1, 2].map(|i| {
+            self.zenith[i] * self.perez[i as usize].eval(cos_theta, gamma)
+                / self.perez[i as usize].eval(1.0, theta_s)
+        });
+        if y <= 0.0 {
+            return Color::default();
+        }
+
+        let xyz = Vec3::new(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let rgb = xyz_to_rgb(xyz);
+        self.intensity * Color::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+    }
+}
+
+impl Environment for PreethamSky {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let d = direction.unit_vector();
+        if d.y < 0.0 {
+            self.ground
+        } else {
+            self.sky_radiance(&d)
+        }
+    }
+
+    fn sample(&self) -> (Vec3, f64) {
+        (Vec3::random_unit_vector(), 1.0 / (4.0 * PI))
+    }
+
+    fn pdf(&self, _direction: &Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+}
+
+unsafe impl Send for PreethamSky {}
+unsafe impl Sync for PreethamSky {}
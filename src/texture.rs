@@ -1,9 +1,117 @@
-use std::{fmt::Debug, sync::Arc};
+use std::{f64::consts::PI, fmt::Debug, io, path::Path, sync::Arc};
 
-use crate::{color::Color, perlin::Perlin, vec3::Point3};
+use crate::{
+    color::{luminance, Color},
+    image::Image,
+    perlin::{Fractal, Perlin},
+    utils::degrees_to_radians,
+    vec3::{Point3, Vec3},
+    worley::Worley,
+};
 
 pub trait Texture: Debug {
     fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+
+    /// Average of the texture over `footprint` around the lookup point. Textures that
+    /// cannot filter themselves fall back to a point sample.
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        let _ = footprint;
+        self.value(u, v, p)
+    }
+}
+
+/// How a pixel's footprint on a surface changes position and texture coordinates,
+/// per pixel step in x and y. All zero means a point sample.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Footprint {
+    pub dpdx: Vec3,
+    pub dpdy: Vec3,
+    pub dudx: f64,
+    pub dvdx: f64,
+    pub dudy: f64,
+    pub dvdy: f64,
+    /// Unit shading normal at the lookup, facing the incoming ray; zero if unknown.
+    pub normal: Vec3,
+    /// The lookup before instance transforms moved it, if any did.
+    pub object: Option<ObjectSpace>,
+}
+
+impl Footprint {
+    /// Width of the footprint in world space.
+    #[inline]
+    pub fn world_width(&self) -> f64 {
+        self.dpdx.length().max(self.dpdy.length())
+    }
+}
+
+/// Where a hit lies on an instanced object in the object's own space, before
+/// `Translate` and `RotateY` placed it in the world.
+#[derive(Clone, Copy, Debug)]
+pub struct ObjectSpace {
+    pub p: Point3,
+    /// Unit shading normal, facing the incoming ray.
+    pub normal: Vec3,
+    /// Rows of the map taking world directions into object space.
+    rows: [Vec3; 3],
+}
+
+impl ObjectSpace {
+    /// A hit whose world space is still the object's own.
+    pub fn new(p: Point3, normal: Vec3) -> Self {
+        Self {
+            p,
+            normal,
+            rows: [
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ],
+        }
+    }
+
+    /// Object-space direction for the world direction `v`.
+    #[inline]
+    pub fn direction(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(
+            self.rows[0].dot(v),
+            self.rows[1].dot(v),
+            self.rows[2].dot(v),
+        )
+    }
+
+    /// The same hit seen from one more transform out, where `inward` takes directions
+    /// in that outer space to the space this one's world directions live in.
+    pub fn through(self, inward: impl Fn(Vec3) -> Vec3) -> Self {
+        let columns = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ]
+        .map(|e| self.direction(&inward(e)));
+        Self {
+            rows: Self::transpose(columns),
+            ..self
+        }
+    }
+
+    /// The same hit with object space itself transformed: the point moves to `p`, the
+    /// normal to `normal`, and directions by `transform`.
+    pub fn transformed(self, p: Point3, normal: Vec3, transform: impl Fn(Vec3) -> Vec3) -> Self {
+        let columns = Self::transpose(self.rows).map(transform);
+        Self {
+            p,
+            normal,
+            rows: Self::transpose(columns),
+        }
+    }
+
+    fn transpose([x, y, z]: [Vec3; 3]) -> [Vec3; 3] {
+        [
+            Vec3::new(x.x, y.x, z.x),
+            Vec3::new(x.y, y.y, z.y),
+            Vec3::new(x.z, y.z, z.z),
+        ]
+    }
 }
 
 #[derive(Clone, Copy, Default, Debug)]
@@ -71,11 +179,414 @@ impl Texture for CheckerTexture {
             self.odd.value(u, v, p)
         }
     }
+
+    /// Box filters the checks over the footprint's bounding box. The pattern is a product
+    /// of square waves along each axis, so it filters exactly one axis at a time.
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        let q = self.inv_scale * *p;
+        let (dx, dy) = (
+            self.inv_scale * footprint.dpdx,
+            self.inv_scale * footprint.dpdy,
+        );
+        let parity = filtered_square_wave(q.x, dx.x.abs() + dy.x.abs())
+            * filtered_square_wave(q.y, dx.y.abs() + dy.y.abs())
+            * filtered_square_wave(q.z, dx.z.abs() + dy.z.abs());
+
+        let even = self.even.filtered_value(u, v, p, footprint);
+        let odd = self.odd.filtered_value(u, v, p, footprint);
+        0.5 * (1.0 + parity) * even + 0.5 * (1.0 - parity) * odd
+    }
 }
 
 unsafe impl Send for CheckerTexture {}
 unsafe impl Sync for CheckerTexture {}
 
+/// Checkerboard in texture coordinates, with `columns` by `rows` checks over the unit
+/// square, so it follows the surface rather than filling space like `CheckerTexture`.
+#[derive(Clone, Debug)]
+pub struct UvCheckerTexture {
+    columns: f64,
+    rows: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl UvCheckerTexture {
+    pub fn new(columns: f64, rows: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            columns,
+            rows,
+            even,
+            odd,
+        }
+    }
+}
+
+impl From<(f64, f64, &Color, &Color)> for UvCheckerTexture {
+    fn from((columns, rows, c1, c2): (f64, f64, &Color, &Color)) -> Self {
+        UvCheckerTexture::new(
+            columns,
+            rows,
+            Arc::new(SolidColor::new(c1)),
+            Arc::new(SolidColor::new(c2)),
+        )
+    }
+}
+
+impl Texture for UvCheckerTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let u_integer = (self.columns * u).floor() as i64;
+        let v_integer = (self.rows * v).floor() as i64;
+
+        if (u_integer + v_integer).rem_euclid(2) == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        let u_width = self.columns * (footprint.dudx.abs() + footprint.dudy.abs());
+        let v_width = self.rows * (footprint.dvdx.abs() + footprint.dvdy.abs());
+        let parity = filtered_square_wave(self.columns * u, u_width)
+            * filtered_square_wave(self.rows * v, v_width);
+
+        let even = self.even.filtered_value(u, v, p, footprint);
+        let odd = self.odd.filtered_value(u, v, p, footprint);
+        0.5 * (1.0 + parity) * even + 0.5 * (1.0 - parity) * odd
+    }
+}
+
+unsafe impl Send for UvCheckerTexture {}
+unsafe impl Sync for UvCheckerTexture {}
+
+/// Average over [x - width / 2, x + width / 2] of the square wave that is +1 on [0, 1)
+/// and -1 on [1, 2), the building block of the filtered checkerboards.
+fn filtered_square_wave(x: f64, width: f64) -> f64 {
+    if width < 1e-6 {
+        return if x.floor().rem_euclid(2.0) == 0.0 {
+            1.0
+        } else {
+            -1.0
+        };
+    }
+    let integral = |x: f64| 1.0 - (x.rem_euclid(2.0) - 1.0).abs();
+    (integral(x + 0.5 * width) - integral(x - 0.5 * width)) / width
+}
+
+/// Moves a texture on the surface by transforming its lookup coordinates: either the
+/// texture coordinates or the point, depending on which the texture reads. Scaling by 2
+/// repeats the texture twice as often.
+#[derive(Clone, Debug)]
+pub struct TransformTexture {
+    texture: Arc<dyn Texture>,
+    uv_rows: [(f64, f64); 2],
+    uv_offset: (f64, f64),
+    point_rows: [Vec3; 3],
+    point_offset: Vec3,
+}
+
+impl TransformTexture {
+    const IDENTITY_UV: [(f64, f64); 2] = [(1.0, 0.0), (0.0, 1.0)];
+    const IDENTITY_POINT: [Vec3; 3] = [
+        Vec3 {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        },
+        Vec3 {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        },
+        Vec3 {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        },
+    ];
+
+    /// Scales texture coordinates, then rotates them by `rotation` degrees
+    /// counterclockwise, then offsets them.
+    pub fn uv(
+        texture: Arc<dyn Texture>,
+        scale: (f64, f64),
+        rotation: f64,
+        offset: (f64, f64),
+    ) -> Self {
+        let (sin, cos) = degrees_to_radians(rotation).sin_cos();
+        Self {
+            texture,
+            uv_rows: [
+                (cos * scale.0, -sin * scale.1),
+                (sin * scale.0, cos * scale.1),
+            ],
+            uv_offset: offset,
+            point_rows: Self::IDENTITY_POINT,
+            point_offset: Vec3::default(),
+        }
+    }
+
+    /// Scales points, then rotates them by `rotation` degrees about x, y and z in that
+    /// order, then offsets them.
+    pub fn point(texture: Arc<dyn Texture>, scale: Vec3, rotation: Vec3, offset: Vec3) -> Self {
+        let rotate = |axis: i32, degrees: f64| {
+            let (sin, cos) = degrees_to_radians(degrees).sin_cos();
+            let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+            let mut rows = Self::IDENTITY_POINT;
+            rows[a as usize][a] = cos;
+            rows[a as usize][b] = -sin;
+            rows[b as usize][a] = sin;
+            rows[b as usize][b] = cos;
+            rows
+        };
+        let multiply =
+            |m: [Vec3; 3], n: [Vec3; 3]| m.map(|row| row.x * n[0] + row.y * n[1] + row.z * n[2]);
+        let rotation = multiply(
+            rotate(2, rotation.z),
+            multiply(rotate(1, rotation.y), rotate(0, rotation.x)),
+        );
+        Self {
+            texture,
+            uv_rows: Self::IDENTITY_UV,
+            uv_offset: (0.0, 0.0),
+            point_rows: rotation.map(|row| row * scale),
+            point_offset: offset,
+        }
+    }
+
+    #[inline]
+    fn transform_uv(&self, u: f64, v: f64) -> (f64, f64) {
+        let [(a, b), (c, d)] = self.uv_rows;
+        (a * u + b * v, c * u + d * v)
+    }
+
+    #[inline]
+    fn transform_vector(&self, p: &Vec3) -> Vec3 {
+        let [r0, r1, r2] = &self.point_rows;
+        Vec3::new(r0.dot(p), r1.dot(p), r2.dot(p))
+    }
+
+    /// Normals take the inverse transpose, here the cofactor matrix with the sign of the
+    /// determinant, since only the direction matters.
+    fn transform_normal(&self, n: &Vec3) -> Vec3 {
+        let [r0, r1, r2] = &self.point_rows;
+        let cofactor = [r1.cross(r2), r2.cross(r0), r0.cross(r1)];
+        let sign = r0.dot(&cofactor[0]).signum();
+        let n = sign * Vec3::new(cofactor[0].dot(n), cofactor[1].dot(n), cofactor[2].dot(n));
+        if n.near_zero() {
+            n
+        } else {
+            n.unit_vector()
+        }
+    }
+
+    fn transform(&self, u: f64, v: f64, p: &Point3) -> (f64, f64, Point3) {
+        let (u, v) = self.transform_uv(u, v);
+        let p = self.transform_vector(p) + self.point_offset;
+        (u + self.uv_offset.0, v + self.uv_offset.1, p)
+    }
+}
+
+impl Texture for TransformTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let (u, v, p) = self.transform(u, v, p);
+        self.texture.value(u, v, &p)
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        let (u, v, p) = self.transform(u, v, p);
+        let (dudx, dvdx) = self.transform_uv(footprint.dudx, footprint.dvdx);
+        let (dudy, dvdy) = self.transform_uv(footprint.dudy, footprint.dvdy);
+        let footprint = Footprint {
+            dpdx: self.transform_vector(&footprint.dpdx),
+            dpdy: self.transform_vector(&footprint.dpdy),
+            dudx,
+            dvdx,
+            dudy,
+            dvdy,
+            normal: self.transform_normal(&footprint.normal),
+            object: footprint.object.map(|object| {
+                object.transformed(
+                    self.transform_vector(&object.p) + self.point_offset,
+                    self.transform_normal(&object.normal),
+                    |v| self.transform_vector(&v),
+                )
+            }),
+        };
+        self.texture.filtered_value(u, v, &p, &footprint)
+    }
+}
+
+unsafe impl Send for TransformTexture {}
+unsafe impl Sync for TransformTexture {}
+
+/// How a `ProjectionTexture` derives texture coordinates from position and normal,
+/// relative to its center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Straight along z: `u = x`, `v = y`.
+    Planar,
+    /// Planar along whichever axis the normal is closest to, like a box's faces.
+    Box,
+    /// The three axis projections blended by the normal's components raised to
+    /// `sharpness`; higher values narrow the blend at the seams.
+    Triplanar { sharpness: f64 },
+    /// Around the y axis: `u` is the angle as on spheres and `v` the height.
+    Cylindrical,
+    /// Longitude and latitude, matching the coordinates of a `Sphere`.
+    Spherical,
+}
+
+/// Looks up a texture with coordinates projected from the hit position instead of the
+/// object's own, so textures apply to boxes, instances and other shapes without
+/// authored coordinates. Pair it with `TransformTexture::point` to orient or scale the
+/// projection, and `TransformTexture::uv` inside it to tile the texture.
+///
+/// Hits on objects placed by `Translate` or `RotateY` project from their position and
+/// normal before those transforms, so the texture moves with the instance and `center`
+/// is in the object's space. Plain `value` lookups carry no hit and project the world
+/// position.
+#[derive(Clone, Debug)]
+pub struct ProjectionTexture {
+    texture: Arc<dyn Texture>,
+    projection: Projection,
+    center: Point3,
+}
+
+impl ProjectionTexture {
+    pub fn new(texture: Arc<dyn Texture>, projection: Projection, center: Point3) -> Self {
+        Self {
+            texture,
+            projection,
+            center,
+        }
+    }
+
+    fn axis_uv(axis: usize, q: &Vec3) -> (f64, f64) {
+        match axis {
+            0 => (q.z, q.y),
+            1 => (q.x, q.z),
+            _ => (q.x, q.y),
+        }
+    }
+
+    fn angle_u(q: &Vec3) -> f64 {
+        ((-q.z).atan2(q.x) + PI) / (2.0 * PI)
+    }
+
+    /// Projects `q` and its footprint offsets, unwrapping `u` across the seam of the
+    /// angular projections.
+    fn project(
+        q: Vec3,
+        footprint: &Footprint,
+        uv: impl Fn(&Vec3) -> (f64, f64),
+        periodic_u: bool,
+    ) -> (f64, f64, Footprint) {
+        let (u, v) = uv(&q);
+        let delta = |dp: &Vec3| {
+            if dp.near_zero() {
+                return (0.0, 0.0);
+            }
+            let (u1, v1) = uv(&(q + *dp));
+            let du = u1 - u;
+            let du = if periodic_u { du - du.round() } else { du };
+            (du, v1 - v)
+        };
+        let (dudx, dvdx) = delta(&footprint.dpdx);
+        let (dudy, dvdy) = delta(&footprint.dpdy);
+        let footprint = Footprint {
+            dudx,
+            dvdx,
+            dudy,
+            dvdy,
+            ..*footprint
+        };
+        (u, v, footprint)
+    }
+
+    fn sample(
+        &self,
+        p: &Point3,
+        footprint: &Footprint,
+        lookup: impl Fn(&dyn Texture, f64, f64, &Footprint) -> Color,
+    ) -> Color {
+        let (p, footprint) = match footprint.object {
+            Some(object) => (
+                object.p,
+                Footprint {
+                    dpdx: object.direction(&footprint.dpdx),
+                    dpdy: object.direction(&footprint.dpdy),
+                    normal: object.normal,
+                    ..*footprint
+                },
+            ),
+            None => (*p, *footprint),
+        };
+        let footprint = &footprint;
+        let q = p - self.center;
+        let texture = self.texture.as_ref();
+        let planar = |axis: usize| {
+            let (u, v, fp) = Self::project(q, footprint, |q| Self::axis_uv(axis, q), false);
+            lookup(texture, u, v, &fp)
+        };
+        // Without a normal, as for plain `value` lookups, the direction from the center
+        // stands in for it.
+        let normal = if footprint.normal.near_zero() {
+            q
+        } else {
+            footprint.normal
+        };
+        let weights = [normal.x.abs(), normal.y.abs(), normal.z.abs()];
+
+        match self.projection {
+            Projection::Planar => planar(2),
+            Projection::Box => {
+                let axis = (0..3).fold(0, |a, b| if weights[b] > weights[a] { b } else { a });
+                planar(axis)
+            }
+            Projection::Triplanar { sharpness } => {
+                let weights = weights.map(|w| w.powf(sharpness));
+                let total: f64 = weights.iter().sum();
+                if total <= 0.0 {
+                    return planar(2);
+                }
+                (0..3)
+                    .filter(|&axis| weights[axis] > 0.0)
+                    .map(|axis| (weights[axis] / total) * planar(axis))
+                    .sum()
+            }
+            Projection::Cylindrical => {
+                let (u, v, fp) = Self::project(q, footprint, |q| (Self::angle_u(q), q.y), true);
+                lookup(texture, u, v, &fp)
+            }
+            Projection::Spherical => {
+                let uv = |q: &Vec3| {
+                    let cos_theta = -q.y / q.length().max(1e-12);
+                    (Self::angle_u(q), cos_theta.clamp(-1.0, 1.0).acos() / PI)
+                };
+                let (u, v, fp) = Self::project(q, footprint, uv, true);
+                lookup(texture, u, v, &fp)
+            }
+        }
+    }
+}
+
+impl Texture for ProjectionTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        self.sample(p, &Footprint::default(), |tex, u, v, _| tex.value(u, v, p))
+    }
+
+    fn filtered_value(&self, _u: f64, _v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        self.sample(p, footprint, |tex, u, v, fp| {
+            tex.filtered_value(u, v, p, fp)
+        })
+    }
+}
+
+unsafe impl Send for ProjectionTexture {}
+unsafe impl Sync for ProjectionTexture {}
+
 #[derive(Debug, Clone)]
 pub struct NoiseTexture {
     noise: Perlin,
@@ -84,18 +595,791 @@ pub struct NoiseTexture {
 
 impl NoiseTexture {
     pub fn new(scale: f64) -> Self {
-        Self {
-            noise: Perlin::new(),
-            scale,
-        }
+        Self::with_noise(scale, Perlin::new())
+    }
+
+    /// Marble over the given noise, e.g. a seeded `Perlin` for reproducible renders.
+    pub fn with_noise(scale: f64, noise: Perlin) -> Self {
+        Self { noise, scale }
+    }
+}
+
+impl NoiseTexture {
+    const OCTAVES: i32 = 7;
+
+    fn marble(&self, p: &Point3, octaves: i32) -> f64 {
+        0.5 * (1.0 + (self.scale * p.z + 10.0 * self.noise.turb(*p, octaves)).sin())
     }
 }
 
 impl Texture for NoiseTexture {
     fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
-        Color::new(0.5, 0.5, 0.5) * (1.0 + (self.scale * p.z + 10.0 * self.noise.turb(*p, 7)).sin())
+        let value = self.marble(p, Self::OCTAVES);
+        Color::new(value, value, value)
+    }
+
+    /// Drops the turbulence octaves finer than the footprint and fades the stripes to
+    /// their mean grey once several of them fit inside it.
+    fn filtered_value(&self, _u: f64, _v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        let width = footprint.world_width();
+        let octaves = if width > 0.0 {
+            ((1.0 - width.log2()) as i32).clamp(1, Self::OCTAVES)
+        } else {
+            Self::OCTAVES
+        };
+        let fade = (width * self.scale / PI).clamp(0.0, 1.0);
+        let value = (1.0 - fade) * self.marble(p, octaves) + fade * 0.5;
+        Color::new(value, value, value)
     }
 }
 
 unsafe impl Send for NoiseTexture {}
 unsafe impl Sync for NoiseTexture {}
+
+/// Piecewise linear map from [0, 1] to colors, holding the end colors outside the
+/// first and last stops.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorRamp {
+    stops: Vec<(f64, Color)>,
+}
+
+impl ColorRamp {
+    pub fn new(mut stops: Vec<(f64, Color)>) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    pub fn value(&self, t: f64) -> Color {
+        let Some(&(first_t, first)) = self.stops.first() else {
+            return Color::new(t, t, t);
+        };
+        if t <= first_t {
+            return first;
+        }
+
+        let i = self.stops.partition_point(|&(s, _)| s <= t);
+        match self.stops.get(i) {
+            None => self.stops[i - 1].1,
+            Some(&(t1, c1)) => {
+                let (t0, c0) = self.stops[i - 1];
+                let f = (t - t0) / (t1 - t0);
+                (1.0 - f) * c0 + f * c1
+            }
+        }
+    }
+}
+
+impl Default for ColorRamp {
+    /// Black to white.
+    fn default() -> Self {
+        Self::new(vec![
+            (0.0, Color::new(0.0, 0.0, 0.0)),
+            (1.0, Color::new(1.0, 1.0, 1.0)),
+        ])
+    }
+}
+
+impl From<Vec<(f64, Color)>> for ColorRamp {
+    fn from(stops: Vec<(f64, Color)>) -> Self {
+        ColorRamp::new(stops)
+    }
+}
+
+/// Scalar field in roughly [0, 1] that a `PatternTexture` maps through its ramp.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoisePattern {
+    /// fBm remapped from [-1, 1]; soft clouds.
+    Fbm(Fractal),
+    /// Sum of absolute octaves; billows and veins.
+    Turbulence(Fractal),
+    /// Ridged multifractal; mountain crests and cracked stone.
+    Ridged(Fractal),
+    /// Distance to the nearest cellular feature point.
+    WorleyF1,
+    /// Distance to the second nearest cellular feature point.
+    WorleyF2,
+    /// `F2 - F1`, zero along cell borders; flagstones and mortar.
+    WorleyEdge,
+    /// A random value per cell.
+    WorleyCell,
+    /// Concentric rings around the y axis, one per unit of radius; warped, wood grain.
+    Rings,
+}
+
+/// A procedural pattern, optionally domain warped, colored through a ramp. Points are
+/// scaled by `scale` before evaluation, so larger values give finer detail.
+#[derive(Clone, Debug)]
+pub struct PatternTexture {
+    perlin: Perlin,
+    worley: Worley,
+    pattern: NoisePattern,
+    scale: f64,
+    ramp: ColorRamp,
+    warp: Option<(f64, Fractal)>,
+    time: Option<f64>,
+}
+
+impl PatternTexture {
+    pub fn new(pattern: NoisePattern, scale: f64, ramp: ColorRamp) -> Self {
+        Self {
+            perlin: Perlin::new(),
+            worley: Worley::new(),
+            pattern,
+            scale,
+            ramp,
+            warp: None,
+            time: None,
+        }
+    }
+
+    /// Seeds both noise sources so the pattern is the same on every run.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.perlin = Perlin::with_seed(seed);
+        self.worley = Worley::with_seed(seed);
+        self
+    }
+
+    /// Uses the given gradient noise, e.g. a periodic one for tiling fractal patterns.
+    pub fn with_perlin(mut self, perlin: Perlin) -> Self {
+        self.perlin = perlin;
+        self
+    }
+
+    /// Evaluates the fractal patterns at time `t` in 4D noise, so stepping `t` between
+    /// frames makes them evolve in place.
+    pub fn at_time(mut self, t: f64) -> Self {
+        self.time = Some(t);
+        self
+    }
+
+    /// Displaces lookups by fBm of the scaled point, `strength` units at most roughly.
+    pub fn with_warp(mut self, strength: f64, fractal: Fractal) -> Self {
+        self.warp = Some((strength, fractal));
+        self
+    }
+
+    fn pattern(&self, p: &Point3, pattern: &NoisePattern) -> f64 {
+        let mut q = self.scale * *p;
+        if let Some((strength, fractal)) = &self.warp {
+            q = self.perlin.warp(q, *strength, fractal);
+        }
+
+        match (pattern, self.time) {
+            (NoisePattern::Fbm(fractal), None) => 0.5 * (1.0 + self.perlin.fbm(q, fractal)),
+            (NoisePattern::Fbm(fractal), Some(t)) => {
+                0.5 * (1.0 + self.perlin.fbm_4d(q, t, fractal))
+            }
+            (NoisePattern::Turbulence(fractal), None) => self.perlin.turbulence(q, fractal),
+            (NoisePattern::Turbulence(fractal), Some(t)) => {
+                self.perlin.turbulence_4d(q, t, fractal)
+            }
+            (NoisePattern::Ridged(fractal), None) => self.perlin.ridged(q, fractal),
+            (NoisePattern::Ridged(fractal), Some(t)) => self.perlin.ridged_4d(q, t, fractal),
+            (NoisePattern::WorleyF1, _) => self.worley.noise(q).f1,
+            (NoisePattern::WorleyF2, _) => self.worley.noise(q).f2,
+            (NoisePattern::WorleyEdge, _) => {
+                let sample = self.worley.noise(q);
+                sample.f2 - sample.f1
+            }
+            (NoisePattern::WorleyCell, _) => self.worley.noise(q).cell,
+            (NoisePattern::Rings, _) => {
+                let radius = (q.x * q.x + q.z * q.z).sqrt();
+                radius - radius.floor()
+            }
+        }
+    }
+}
+
+impl Texture for PatternTexture {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        self.ramp.value(self.pattern(p, &self.pattern))
+    }
+
+    /// Drops the fractal octaves finer than the footprint; the cellular patterns and
+    /// rings are left unfiltered.
+    fn filtered_value(&self, _u: f64, _v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        let width = footprint.world_width();
+        let pattern = match self.pattern {
+            NoisePattern::Fbm(fractal) => {
+                NoisePattern::Fbm(fractal.band_limited(self.scale, width))
+            }
+            NoisePattern::Turbulence(fractal) => {
+                NoisePattern::Turbulence(fractal.band_limited(self.scale, width))
+            }
+            NoisePattern::Ridged(fractal) => {
+                NoisePattern::Ridged(fractal.band_limited(self.scale, width))
+            }
+            pattern => pattern,
+        };
+        self.ramp.value(self.pattern(p, &pattern))
+    }
+}
+
+unsafe impl Send for PatternTexture {}
+unsafe impl Sync for PatternTexture {}
+
+/// How texture coordinates outside [0, 1] map back onto an image.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    /// Maps texel index `i` into `0..n`.
+    #[inline]
+    pub fn wrap(&self, i: i64, n: usize) -> usize {
+        let n = n as i64;
+        let i = match self {
+            Self::Repeat => i.rem_euclid(n),
+            Self::Clamp => i.clamp(0, n - 1),
+            Self::Mirror => {
+                let m = i.rem_euclid(2 * n);
+                if m >= n {
+                    2 * n - 1 - m
+                } else {
+                    m
+                }
+            }
+        };
+        i as usize
+    }
+}
+
+/// How image textures average texels over a footprint.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TextureFilter {
+    /// Bilinear lookups in the full resolution image, ignoring the footprint.
+    Bilinear,
+    /// Blend of bilinear lookups in the two mipmap levels closest to the footprint width.
+    #[default]
+    Trilinear,
+    /// Elliptically weighted average over the footprint, sharper at grazing angles.
+    Ewa,
+}
+
+/// Image mapped by texture coordinates, with `v` running up from the bottom row. Lookups
+/// are filtered from a mipmap pyramid according to the hit's footprint.
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+    levels: Vec<Image>,
+    wrap: WrapMode,
+    filter: TextureFilter,
+}
+
+impl ImageTexture {
+    /// Longest an EWA ellipse may be relative to its width before it is fattened.
+    const MAX_ANISOTROPY: f64 = 8.0;
+
+    pub fn new(image: Image, wrap: WrapMode) -> Self {
+        Self::with_filter(image, wrap, TextureFilter::default())
+    }
+
+    pub fn with_filter(image: Image, wrap: WrapMode, filter: TextureFilter) -> Self {
+        let mut levels = vec![image];
+        loop {
+            let last = &levels[levels.len() - 1];
+            // An empty image has nothing to average, and lookups in it return the
+            // debugging color anyway.
+            if (last.width <= 1 && last.height <= 1) || last.width == 0 || last.height == 0 {
+                break;
+            }
+            let next = Self::downsample(last, wrap);
+            levels.push(next);
+        }
+        Self {
+            levels,
+            wrap,
+            filter,
+        }
+    }
+
+    /// Loads an image file as color: PPM and PNG files are decoded from sRGB.
+    pub fn load(path: impl AsRef<Path>, wrap: WrapMode) -> io::Result<Self> {
+        Ok(Self::new(Image::load(path)?, wrap))
+    }
+
+    /// Loads an image file as data, e.g. a normal map, without sRGB decoding.
+    pub fn load_linear(path: impl AsRef<Path>, wrap: WrapMode) -> io::Result<Self> {
+        Ok(Self::new(Image::load_linear(path)?, wrap))
+    }
+
+    /// Loads the alpha channel of a PNG file as grey, for opacity masks.
+    pub fn load_alpha(path: impl AsRef<Path>, wrap: WrapMode) -> io::Result<Self> {
+        Ok(Self::new(Image::load_alpha(path)?, wrap))
+    }
+
+    /// Next mipmap level: half the size, rounded up, averaging 2x2 blocks.
+    fn downsample(image: &Image, wrap: WrapMode) -> Image {
+        let (width, height) = (
+            image.width.div_ceil(2).max(1),
+            image.height.div_ceil(2).max(1),
+        );
+        let data = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x as i64, y as i64)))
+            .map(|(x, y)| {
+                let texel = |dx: i64, dy: i64| {
+                    image.pixel(
+                        wrap.wrap(2 * x + dx, image.width),
+                        wrap.wrap(2 * y + dy, image.height),
+                    )
+                };
+                0.25 * (texel(0, 0) + texel(1, 0) + texel(0, 1) + texel(1, 1))
+            })
+            .collect();
+        Image::new(width, height, data)
+    }
+
+    fn texel(&self, level: usize, x: i64, y: i64) -> Color {
+        let image = &self.levels[level];
+        image.pixel(
+            self.wrap.wrap(x, image.width),
+            self.wrap.wrap(y, image.height),
+        )
+    }
+
+    /// Bilinear lookup at image coordinates `s`, `t` in [0, 1] from the top left.
+    fn bilinear(&self, level: usize, s: f64, t: f64) -> Color {
+        let image = &self.levels[level];
+        let x = s * image.width as f64 - 0.5;
+        let y = t * image.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = (1.0 - fx) * self.texel(level, x0, y0) + fx * self.texel(level, x0 + 1, y0);
+        let bottom =
+            (1.0 - fx) * self.texel(level, x0, y0 + 1) + fx * self.texel(level, x0 + 1, y0 + 1);
+        (1.0 - fy) * top + fy * bottom
+    }
+
+    /// Continuous mipmap level whose texels are `width` wide in texture space.
+    fn level_for(&self, width: f64) -> f64 {
+        let resolution = self.levels[0].width.max(self.levels[0].height) as f64;
+        (width * resolution).max(1e-8).log2().max(0.0)
+    }
+
+    fn trilinear(&self, s: f64, t: f64, width: f64) -> Color {
+        let level = self.level_for(width);
+        let last = self.levels.len() - 1;
+        if level >= last as f64 {
+            return self.texel(last, 0, 0);
+        }
+        let lower = level.floor();
+        let frac = level - lower;
+        let lower = lower as usize;
+        (1.0 - frac) * self.bilinear(lower, s, t) + frac * self.bilinear(lower + 1, s, t)
+    }
+
+    /// Elliptically weighted average with a Gaussian falloff, after Heckbert and pbrt.
+    fn ewa(&self, s: f64, t: f64, mut major: (f64, f64), mut minor: (f64, f64)) -> Color {
+        let length = |a: (f64, f64)| (a.0 * a.0 + a.1 * a.1).sqrt();
+        if length(major) < length(minor) {
+            std::mem::swap(&mut major, &mut minor);
+        }
+        let (major_length, mut minor_length) = (length(major), length(minor));
+
+        // Fatten overly eccentric ellipses so the lookup stays bounded.
+        if minor_length * Self::MAX_ANISOTROPY < major_length && minor_length > 0.0 {
+            let scale = major_length / (minor_length * Self::MAX_ANISOTROPY);
+            minor = (minor.0 * scale, minor.1 * scale);
+            minor_length *= scale;
+        }
+        if minor_length == 0.0 {
+            return self.bilinear(0, s, t);
+        }
+
+        let level = self.level_for(minor_length);
+        let lower = level.floor();
+        let frac = level - lower;
+        let lower = lower as usize;
+        (1.0 - frac) * self.ewa_level(lower, s, t, major, minor)
+            + frac * self.ewa_level(lower + 1, s, t, major, minor)
+    }
+
+    fn ewa_level(&self, level: usize, s: f64, t: f64, d0: (f64, f64), d1: (f64, f64)) -> Color {
+        let Some(image) = self.levels.get(level) else {
+            return self.texel(self.levels.len() - 1, 0, 0);
+        };
+        let (w, h) = (image.width as f64, image.height as f64);
+        let (x_center, y_center) = (s * w - 0.5, t * h - 0.5);
+        let (d0, d1) = ((d0.0 * w, d0.1 * h), (d1.0 * w, d1.1 * h));
+
+        // Implicit ellipse a*x^2 + b*x*y + c*y^2 = 1 spanned by the two axes.
+        let mut a = d0.1 * d0.1 + d1.1 * d1.1 + 1.0;
+        let mut b = -2.0 * (d0.0 * d0.1 + d1.0 * d1.1);
+        let mut c = d0.0 * d0.0 + d1.0 * d1.0 + 1.0;
+        let inv_f = 1.0 / (a * c - 0.25 * b * b);
+        a *= inv_f;
+        b *= inv_f;
+        c *= inv_f;
+
+        let det = 4.0 * a * c - b * b;
+        let (s_extent, t_extent) = (2.0 * (det * c).sqrt() / det, 2.0 * (det * a).sqrt() / det);
+        let (x0, x1) = (
+            (x_center - s_extent).ceil() as i64,
+            (x_center + s_extent).floor() as i64,
+        );
+        let (y0, y1) = (
+            (y_center - t_extent).ceil() as i64,
+            (y_center + t_extent).floor() as i64,
+        );
+
+        const ALPHA: f64 = 2.0;
+        let mut sum = Color::default();
+        let mut weights = 0.0;
+        for y in y0..=y1 {
+            let dt = y as f64 - y_center;
+            for x in x0..=x1 {
+                let ds = x as f64 - x_center;
+                let r2 = a * ds * ds + b * ds * dt + c * dt * dt;
+                if r2 < 1.0 {
+                    let weight = (-ALPHA * r2).exp() - (-ALPHA).exp();
+                    sum += weight * self.texel(level, x, y);
+                    weights += weight;
+                }
+            }
+        }
+        if weights > 0.0 {
+            sum / weights
+        } else {
+            self.bilinear(level, s, t)
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: &Point3) -> Color {
+        // Solid cyan as a debugging aid when there is no image.
+        if self.levels[0].width == 0 || self.levels[0].height == 0 {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        self.bilinear(0, u, 1.0 - v)
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        if self.levels[0].width == 0 || self.levels[0].height == 0 {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        let (s, t) = (u, 1.0 - v);
+        let dx = (footprint.dudx, -footprint.dvdx);
+        let dy = (footprint.dudy, -footprint.dvdy);
+        match self.filter {
+            TextureFilter::Bilinear => self.value(u, v, p),
+            TextureFilter::Trilinear => {
+                let width = 2.0 * dx.0.abs().max(dx.1.abs()).max(dy.0.abs()).max(dy.1.abs());
+                self.trilinear(s, t, width)
+            }
+            TextureFilter::Ewa => self.ewa(s, t, dx, dy),
+        }
+    }
+}
+
+unsafe impl Send for ImageTexture {}
+unsafe impl Sync for ImageTexture {}
+
+/// Blends `a` into `b` by the luminance of `factor`: 0 gives `a`, 1 gives `b`.
+#[derive(Clone, Debug)]
+pub struct MixTexture {
+    a: Arc<dyn Texture>,
+    b: Arc<dyn Texture>,
+    factor: Arc<dyn Texture>,
+}
+
+impl MixTexture {
+    pub fn new(a: Arc<dyn Texture>, b: Arc<dyn Texture>, factor: Arc<dyn Texture>) -> Self {
+        Self { a, b, factor }
+    }
+
+    fn combine(&self, lookup: impl Fn(&dyn Texture) -> Color) -> Color {
+        let t = luminance(&lookup(self.factor.as_ref()));
+        (1.0 - t) * lookup(self.a.as_ref()) + t * lookup(self.b.as_ref())
+    }
+}
+
+impl Texture for MixTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.combine(|tex| tex.value(u, v, p))
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        self.combine(|tex| tex.filtered_value(u, v, p, footprint))
+    }
+}
+
+unsafe impl Send for MixTexture {}
+unsafe impl Sync for MixTexture {}
+
+/// Channelwise product of two textures, e.g. a grime mask over an albedo map.
+#[derive(Clone, Debug)]
+pub struct MultiplyTexture {
+    a: Arc<dyn Texture>,
+    b: Arc<dyn Texture>,
+}
+
+impl MultiplyTexture {
+    pub fn new(a: Arc<dyn Texture>, b: Arc<dyn Texture>) -> Self {
+        Self { a, b }
+    }
+}
+
+impl Texture for MultiplyTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.a.value(u, v, p) * self.b.value(u, v, p)
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        self.a.filtered_value(u, v, p, footprint) * self.b.filtered_value(u, v, p, footprint)
+    }
+}
+
+unsafe impl Send for MultiplyTexture {}
+unsafe impl Sync for MultiplyTexture {}
+
+/// Channelwise sum of two textures.
+#[derive(Clone, Debug)]
+pub struct AddTexture {
+    a: Arc<dyn Texture>,
+    b: Arc<dyn Texture>,
+}
+
+impl AddTexture {
+    pub fn new(a: Arc<dyn Texture>, b: Arc<dyn Texture>) -> Self {
+        Self { a, b }
+    }
+}
+
+impl Texture for AddTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.a.value(u, v, p) + self.b.value(u, v, p)
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        self.a.filtered_value(u, v, p, footprint) + self.b.filtered_value(u, v, p, footprint)
+    }
+}
+
+unsafe impl Send for AddTexture {}
+unsafe impl Sync for AddTexture {}
+
+/// `1 - c` in each channel.
+#[derive(Clone, Debug)]
+pub struct InvertTexture {
+    tex: Arc<dyn Texture>,
+}
+
+impl InvertTexture {
+    pub fn new(tex: Arc<dyn Texture>) -> Self {
+        Self { tex }
+    }
+}
+
+impl Texture for InvertTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        Color::new(1.0, 1.0, 1.0) - self.tex.value(u, v, p)
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        Color::new(1.0, 1.0, 1.0) - self.tex.filtered_value(u, v, p, footprint)
+    }
+}
+
+unsafe impl Send for InvertTexture {}
+unsafe impl Sync for InvertTexture {}
+
+/// Linearly maps each channel from `from` onto `to`, e.g. to raise the contrast of a
+/// noise mask before using it as a mix factor. Values outside `from` extrapolate, and an
+/// empty `from` range thresholds at its value.
+#[derive(Clone, Debug)]
+pub struct RemapTexture {
+    tex: Arc<dyn Texture>,
+    from: (f64, f64),
+    to: (f64, f64),
+}
+
+impl RemapTexture {
+    pub fn new(tex: Arc<dyn Texture>, from: (f64, f64), to: (f64, f64)) -> Self {
+        Self { tex, from, to }
+    }
+
+    fn remap(&self, c: Color) -> Color {
+        let width = self.from.1 - self.from.0;
+        let remap = |x: f64| {
+            // An empty source range is a step at its value.
+            let t = if width == 0.0 {
+                if x < self.from.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            } else {
+                (x - self.from.0) / width
+            };
+            self.to.0 + t * (self.to.1 - self.to.0)
+        };
+        Color::new(remap(c.x), remap(c.y), remap(c.z))
+    }
+}
+
+impl Texture for RemapTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.remap(self.tex.value(u, v, p))
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        self.remap(self.tex.filtered_value(u, v, p, footprint))
+    }
+}
+
+unsafe impl Send for RemapTexture {}
+unsafe impl Sync for RemapTexture {}
+
+/// Colors a texture by passing its luminance through a ramp.
+#[derive(Clone, Debug)]
+pub struct RampTexture {
+    tex: Arc<dyn Texture>,
+    ramp: ColorRamp,
+}
+
+impl RampTexture {
+    pub fn new(tex: Arc<dyn Texture>, ramp: ColorRamp) -> Self {
+        Self { tex, ramp }
+    }
+}
+
+impl Texture for RampTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.ramp.value(luminance(&self.tex.value(u, v, p)))
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        self.ramp
+            .value(luminance(&self.tex.filtered_value(u, v, p, footprint)))
+    }
+}
+
+unsafe impl Send for RampTexture {}
+unsafe impl Sync for RampTexture {}
+
+/// Clamps each channel to [min, max].
+#[derive(Clone, Debug)]
+pub struct ClampTexture {
+    tex: Arc<dyn Texture>,
+    min: f64,
+    max: f64,
+}
+
+impl ClampTexture {
+    pub fn new(tex: Arc<dyn Texture>, min: f64, max: f64) -> Self {
+        Self { tex, min, max }
+    }
+
+    fn clamp(&self, c: Color) -> Color {
+        Color::new(
+            c.x.clamp(self.min, self.max),
+            c.y.clamp(self.min, self.max),
+            c.z.clamp(self.min, self.max),
+        )
+    }
+}
+
+impl Texture for ClampTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.clamp(self.tex.value(u, v, p))
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        self.clamp(self.tex.filtered_value(u, v, p, footprint))
+    }
+}
+
+unsafe impl Send for ClampTexture {}
+unsafe impl Sync for ClampTexture {}
+
+/// Which scalar a `ChannelTexture` reads from a color.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    #[default]
+    Luminance,
+}
+
+impl Channel {
+    pub fn of(&self, c: &Color) -> f64 {
+        match self {
+            Self::Red => c.x,
+            Self::Green => c.y,
+            Self::Blue => c.z,
+            Self::Luminance => luminance(c),
+        }
+    }
+}
+
+/// One channel of a texture as a grey color, e.g. a mask packed into an image's red.
+#[derive(Clone, Debug)]
+pub struct ChannelTexture {
+    tex: Arc<dyn Texture>,
+    channel: Channel,
+}
+
+impl ChannelTexture {
+    pub fn new(tex: Arc<dyn Texture>, channel: Channel) -> Self {
+        Self { tex, channel }
+    }
+}
+
+impl Texture for ChannelTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let x = self.channel.of(&self.tex.value(u, v, p));
+        Color::new(x, x, x)
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        let x = self
+            .channel
+            .of(&self.tex.filtered_value(u, v, p, footprint));
+        Color::new(x, x, x)
+    }
+}
+
+unsafe impl Send for ChannelTexture {}
+unsafe impl Sync for ChannelTexture {}
+
+/// Builds a color from the luminances of three scalar textures.
+#[derive(Clone, Debug)]
+pub struct CombineTexture {
+    red: Arc<dyn Texture>,
+    green: Arc<dyn Texture>,
+    blue: Arc<dyn Texture>,
+}
+
+impl CombineTexture {
+    pub fn new(red: Arc<dyn Texture>, green: Arc<dyn Texture>, blue: Arc<dyn Texture>) -> Self {
+        Self { red, green, blue }
+    }
+
+    fn combine(&self, lookup: impl Fn(&dyn Texture) -> Color) -> Color {
+        Color::new(
+            luminance(&lookup(self.red.as_ref())),
+            luminance(&lookup(self.green.as_ref())),
+            luminance(&lookup(self.blue.as_ref())),
+        )
+    }
+}
+
+impl Texture for CombineTexture {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.combine(|tex| tex.value(u, v, p))
+    }
+
+    fn filtered_value(&self, u: f64, v: f64, p: &Point3, footprint: &Footprint) -> Color {
+        self.combine(|tex| tex.filtered_value(u, v, p, footprint))
+    }
+}
+
+unsafe impl Send for CombineTexture {}
+unsafe impl Sync for CombineTexture {}
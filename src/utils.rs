@@ -21,3 +21,15 @@ pub fn random_range(min: f64, max: f64) -> f64 {
 pub fn random_int(min: i32, max: i32) -> i32 {
     random_range(min as f64, max as f64 + 1.0) as i32
 }
+
+/// Multiple importance sampling weight for a sample drawn with pdf `f` when the same
+/// direction could also have been drawn with pdf `g`.
+#[inline]
+pub fn power_heuristic(f: f64, g: f64) -> f64 {
+    let (f2, g2) = (f * f, g * g);
+    if f2 + g2 > 0.0 {
+        f2 / (f2 + g2)
+    } else {
+        0.0
+    }
+}
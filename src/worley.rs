@@ -0,0 +1,98 @@
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::vec3::{Point3, Vec3};
+
+/// Distances from a point to the nearest and second nearest feature points of cellular
+/// noise, with a random value in [0, 1) identifying the nearest point's cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WorleySample {
+    pub f1: f64,
+    pub f2: f64,
+    pub cell: f64,
+}
+
+/// Worley (cellular) noise with one jittered feature point per unit cell.
+#[derive(Clone, Debug)]
+pub struct Worley {
+    points: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Worley {
+    pub fn new() -> Self {
+        Self::from_rng(&mut thread_rng())
+    }
+
+    /// Cells that are identical for every run with the same `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::from_rng(&mut StdRng::seed_from_u64(seed))
+    }
+
+    fn from_rng(rng: &mut impl Rng) -> Self {
+        let points = (0..256)
+            .map(|_| Vec3::new(rng.gen(), rng.gen(), rng.gen()))
+            .collect();
+
+        Self {
+            points,
+            perm_x: Worley::generate_perm(rng),
+            perm_y: Worley::generate_perm(rng),
+            perm_z: Worley::generate_perm(rng),
+        }
+    }
+
+    pub fn noise(&self, p: Point3) -> WorleySample {
+        let i = p.x.floor() as i32;
+        let j = p.y.floor() as i32;
+        let k = p.z.floor() as i32;
+
+        let mut sample = WorleySample {
+            f1: f64::INFINITY,
+            f2: f64::INFINITY,
+            cell: 0.0,
+        };
+
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let (ci, cj, ck) = (i + di, j + dj, k + dk);
+                    let hash = self.perm_x[(ci & 255) as usize]
+                        ^ self.perm_y[(cj & 255) as usize]
+                        ^ self.perm_z[(ck & 255) as usize];
+                    let feature = Vec3::new(ci as f64, cj as f64, ck as f64) + self.points[hash];
+                    let distance = (feature - p).length();
+
+                    if distance < sample.f1 {
+                        sample.f2 = sample.f1;
+                        sample.f1 = distance;
+                        sample.cell = hash as f64 / 256.0;
+                    } else if distance < sample.f2 {
+                        sample.f2 = distance;
+                    }
+                }
+            }
+        }
+
+        sample
+    }
+
+    fn generate_perm(rng: &mut impl Rng) -> Vec<usize> {
+        let mut p: Vec<usize> = (0..256).collect();
+        for i in (0..p.len()).rev() {
+            let target = rng.gen_range(0..=i);
+            p.swap(i, target);
+        }
+        p
+    }
+}
+
+impl Default for Worley {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl Send for Worley {}
+unsafe impl Sync for Worley {}